@@ -0,0 +1,21 @@
+/*
+Benchmarks of the simulation loop (native only, run with `cargo bench`)
+
+Contains:
+ - harmonic_loop: harmonic oscillator (H) integrated for 1e6 steps
+*/
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use diatomic_molecule_simulation_widget::native::{simulate_molecule, SimulationParameters};
+
+// Benchmark of the harmonic oscillator loop at 1e6 steps
+fn harmonic_loop(c: &mut Criterion) {
+    let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 5.0E+05, 0.5, 300.0);
+    let mut group = c.benchmark_group("simulate_molecule");
+    group.sample_size(10);
+    group.bench_function("harmonic_1e6_steps", |b| b.iter(|| simulate_molecule(&params).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, harmonic_loop);
+criterion_main!(benches);
@@ -0,0 +1,125 @@
+/*
+Module for the error type shared by the simulation and plotting code
+
+Contains:
+ - SimError enum:
+    - one variant per kind of failure (unknown element or model, invalid inputs, missing model parameters, ...)
+    - readable messages through Display
+    - converts into a JsValue (string) so errors reach JavaScript unchanged
+ - tests module (native only):
+    - message of each variant
+*/
+
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+// Define enum for everything that can go wrong in a simulation or plot
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimError {
+    UnknownElement { element: String, suggestion: Option<String> }, // Element symbol not in the table (and the closest supported one)
+    UnknownModel(String),                                  // Model name not recognised
+    UnknownOption { option: &'static str, value: String }, // Unrecognised value of a string option (e.g. "energy unit")
+    InvalidTimestep(f64),                                  // Timestep not positive and finite
+    InvalidDuration(f64),                                  // Duration negative or not finite
+    InvalidTemperature(f64),                               // Temperature negative or not finite
+    TooManySteps { steps: f64, max_steps: f64 },           // Run longer than the step limit
+    ZeroForceConstant(String),                             // Element without a harmonic force constant
+    ModelParamsMissing { element: String, model: String }, // Element without the parameters of a model
+    MissingEquilibriumDistance(String),                    // Element without an equilibrium distance
+    NotApplicable { quantity: &'static str, model: String }, // Quantity not defined for the selected model
+    NonFinite(&'static str),                               // Input containing NaN or infinite values
+    InvalidInput(String),                                  // Any other invalid argument (message describes it)
+    NoConvergence(String),                                 // Iterative measurement that did not finish
+    Serialization(String),                                 // Result could not be serialized
+    CanvasNotFound(String),                                // No element with the canvas ID
+    NotACanvas { id: String, tag: String },                // Element with the canvas ID is not a <canvas>
+    Plot(String),                                          // Plotters drawing failure
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::UnknownElement { element, suggestion: None } => write!(f, "Element not supported: {}", element),
+            SimError::UnknownElement { element, suggestion: Some(suggestion) } => {
+                write!(f, "Element not supported: {} (did you mean {}?)", element, suggestion)
+            }
+            SimError::UnknownModel(model) => write!(f, "Unsupported model: {}", model),
+            SimError::UnknownOption { option, value } => write!(f, "Unsupported {}: {}", option, value),
+            SimError::InvalidTimestep(timestep) => write!(f, "Timestep must be positive and finite (got {})", timestep),
+            SimError::InvalidDuration(duration) => write!(f, "Duration must be non-negative and finite (got {})", duration),
+            SimError::InvalidTemperature(temperature) => write!(f, "Temperature must be non-negative and finite (got {})", temperature),
+            SimError::TooManySteps { steps, max_steps } => write!(
+                f, "Duration / timestep gives {:e} steps, more than the maximum of {:e}", steps, max_steps
+            ),
+            SimError::ZeroForceConstant(element) => write!(f, "Harmonic force constant is zero for element {}", element),
+            SimError::ModelParamsMissing { element, model } => write!(f, "Element {} has no parameters for the {} model", element, model),
+            SimError::MissingEquilibriumDistance(element) => write!(f, "Element {} has no equilibrium distance", element),
+            SimError::NotApplicable { quantity, model } => write!(f, "{} is not defined for the {} model", quantity, model),
+            SimError::NonFinite(what) => write!(f, "{} contains non-finite values", what),
+            SimError::InvalidInput(message)
+            | SimError::NoConvergence(message)
+            | SimError::Plot(message) => write!(f, "{}", message),
+            SimError::Serialization(message) => write!(f, "Cannot serialize simulation result: {}", message),
+            SimError::CanvasNotFound(id) => write!(f, "Cannot find canvas with id {}", id),
+            SimError::NotACanvas { id, tag } => write!(f, "Element '{}' is not a canvas (found {})", id, tag),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+// Errors are passed to JavaScript as their message string
+impl From<SimError> for JsValue {
+    fn from(error: SimError) -> JsValue {
+        JsValue::from_str(&error.to_string())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    // Display: each variant maps to its message
+    #[test]
+    fn each_variant_has_its_message() {
+        let cases = [
+            (SimError::UnknownElement { element: String::from("Xx"), suggestion: None }, "Element not supported: Xx"),
+            (
+                SimError::UnknownElement { element: String::from("h"), suggestion: Some(String::from("H")) },
+                "Element not supported: h (did you mean H?)",
+            ),
+            (SimError::UnknownModel(String::from("quartic")), "Unsupported model: quartic"),
+            (SimError::UnknownOption { option: "energy unit", value: String::from("J") }, "Unsupported energy unit: J"),
+            (SimError::InvalidTimestep(-0.5), "Timestep must be positive and finite (got -0.5)"),
+            (SimError::InvalidDuration(f64::NAN), "Duration must be non-negative and finite (got NaN)"),
+            (SimError::InvalidTemperature(-1.0), "Temperature must be non-negative and finite (got -1)"),
+            (
+                SimError::TooManySteps { steps: 2e9, max_steps: 1e8 },
+                "Duration / timestep gives 2e9 steps, more than the maximum of 1e8",
+            ),
+            (SimError::ZeroForceConstant(String::from("Ar")), "Harmonic force constant is zero for element Ar"),
+            (
+                SimError::ModelParamsMissing { element: String::from("Ar"), model: String::from("morse") },
+                "Element Ar has no parameters for the morse model",
+            ),
+            (SimError::MissingEquilibriumDistance(String::from("Hg")), "Element Hg has no equilibrium distance"),
+            (
+                SimError::NotApplicable { quantity: "Anharmonicity", model: String::from("harmonic") },
+                "Anharmonicity is not defined for the harmonic model",
+            ),
+            (SimError::NonFinite("Potential table"), "Potential table contains non-finite values"),
+            (SimError::InvalidInput(String::from("Bad input")), "Bad input"),
+            (SimError::NoConvergence(String::from("No period")), "No period"),
+            (SimError::Serialization(String::from("bad float")), "Cannot serialize simulation result: bad float"),
+            (SimError::CanvasNotFound(String::from("plot")), "Cannot find canvas with id plot"),
+            (
+                SimError::NotACanvas { id: String::from("foo"), tag: String::from("div") },
+                "Element 'foo' is not a canvas (found div)",
+            ),
+            (SimError::Plot(String::from("Cannot draw mesh")), "Cannot draw mesh"),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/*
+Module for the floating-point types the integrator can run in
+
+Contains:
+ - Float trait:
+    - the arithmetic and math functions needed by the force laws and the Velocity Verlet step
+    - conversions from f32 / f64 constants and to f64 (results are always stored as f64)
+    - implemented for f32 (fast, the default precision) and f64 (accurate)
+*/
+
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+// Floating-point type of the integration (see SimulationParameters precision)
+pub(crate) trait Float:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+{
+    fn from_f32(value: f32) -> Self;
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn max(self, other: Self) -> Self;
+}
+
+// Implement the trait by forwarding to the inherent methods of the primitive type
+macro_rules! impl_float {
+    ($t:ty) => {
+        impl Float for $t {
+            fn from_f32(value: f32) -> Self { value as $t }
+            fn from_f64(value: f64) -> Self { value as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+            fn sqrt(self) -> Self { <$t>::sqrt(self) }
+            fn exp(self) -> Self { <$t>::exp(self) }
+            fn powi(self, n: i32) -> Self { <$t>::powi(self, n) }
+            fn max(self, other: Self) -> Self { <$t>::max(self, other) }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
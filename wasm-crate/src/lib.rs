@@ -4,41 +4,628 @@ Main library module for the WebAssembly simulation and plotting of diatomic mole
 Contains:
  - Re-exports:
     - SimulationParameters struct from the sim module for use in JavaScript
+    - PlotOptions and AxisStyle structs from the plt module for use in JavaScript
+    - Simulation struct from the stream module (stepwise simulation for animations)
+    - native module (non-wasm targets only): the simulation API without JsValue, for benchmarks
+ - Start function:
+    - start: runs when the module is initialized, installing the panic hook (if the feature is enabled)
  - Main function:
     - simulate_and_plot: orchestrates the simulation and plotting process
-        - Takes simulation parameters and canvas IDs for energy and displacement plots
+        - Takes simulation parameters, canvas IDs for energy and displacement plots and optional plot options
         - Runs the simulation using the sim module
         - Renders energy and displacement plots using the plt module
         - Returns simulation results to JavaScript for further use
+        - A plot that fails does not fail the call: its message is listed in the plot_errors field of the result
+        - Marks earlier in-flight renders stale (see cancel_pending)
+ - Other functions:
+    - simulate: runs the simulation only and returns the results (no DOM access, so it also runs in Node.js)
+    - simulate_and_plot_offscreen: like simulate_and_plot, drawing on OffscreenCanvas objects (no DOM needed)
+    - simulate_to_svg (feature "svg"): runs the simulation and returns the energy or displacement plot as SVG markup
+    - cancel_pending: marks in-flight renders stale so they skip presenting
+    - element_info: returns the category and suitable models of an element
+    - is_supported_pair: checks whether two elements form a diatomic with tabulated parameters
+    - is_anharmonic: checks whether a model is anharmonic (all but the harmonic oscillator)
+    - get_element_properties_js: returns all the physical constants of an element
+    - simulate_continue: continues a previous simulation result from its last sample
+    - initial_state: returns the initial state of the selected model without running the simulation
+    - simulate_to_json: runs the simulation only and returns the results as a JSON string
+    - simulate_to_json_fields: like simulate_to_json, but only with the named fields
+    - simulate_to_columns: runs the simulation only and returns the series as one flat Float64Array
+    - simulate_summary: runs the simulation only and returns scalar summary statistics
+    - sample_at: interpolates a returned simulation result at any time
+    - relaxation_time: returns when the amplitude of a returned simulation result decays to 1/e (if it does)
+    - ke_pe_ratio: returns the kinetic to potential energy ratio of a returned simulation result at each sample
+    - oscillation_count: returns the number of full oscillations in a returned simulation result
+    - time_reverse_check: returns the displacement error of a forward and time-reversed run
+    - measure_period: integrates one full oscillation and returns its period
+    - classical_action: returns the action ∮ p dq over one oscillation
+    - anharmonicity: estimates how much the oscillation frequency drops with amplitude
+    - ensemble_average: averages the energies over many randomly initialized runs
+    - autocorrelation: returns the normalized displacement autocorrelation against lag time
+    - power_spectrum: returns the power spectrum of the displacements (peak at the vibrational frequency)
+    - timestep_convergence: runs the simulation at several timesteps and returns (timestep, energy drift) pairs
+    - amplitude_vs_temperature: runs the simulation at several temperatures and returns (temperature, peak displacement) pairs
+    - timestep_for_resolution: returns the timestep giving a number of steps per oscillation period
+    - max_stable_timestep: estimates the largest timestep integrating a run accurately (e.g. to warn about dt)
+    - well_depth / equilibrium_distance: return the well depth and equilibrium bond length
+    - is_bound: checks whether the initial state is below the well depth (no dissociation)
+    - dissociation_fraction: Boltzmann estimate exp(-D / k_B T) of the fraction of dissociated molecules
+    - equipartition_energy: returns ½ k_B T per degree of freedom (atomic units or SI)
+    - vibrational_wavenumber: returns the harmonic vibrational wavenumber in cm⁻¹
+    - force_curve: samples the force of the selected model as (r, F) pairs
+    - turning_points: returns the classical turning points of the initial state
+    - simulate_tabulated: simulates a potential given as sampled (r, V) points
+    - isotope_comparison: simulates an element's potential with several substituted masses
+    - morse_from_spectroscopic / simulate_morse_spectroscopic: Morse parameters from ω_e and ω_e x_e, and a run with them
+    - interpolate_lennard_jones: illustrative Lennard-Jones parameters for an arbitrary mass
+    - render_energy_exchange_plot: simulates and plots kinetic against potential energy
+    - render_displacement_energy_dual: simulates and plots displacement and total energy on dual y-axes
+    - render_model_comparison_plot: overlays the harmonic and Morse trajectories of an element
+    - render_potentials_comparison: overlays the harmonic potentials of several elements
+    - render_residual_plot: simulates a harmonic run and plots its residual against the analytic solution
+ - tests module (native only):
+    - plot errors skip only the failed plot
+    - start run more than once
 */
 
 use wasm_bindgen::prelude::*;
-use serde_wasm_bindgen::to_value;
+use serde::Serialize;
+use serde_wasm_bindgen::{from_value, to_value};
 
 // Module for simulation
 mod sim;
 // Module for plotting
 mod plt;
+// Module for the OffscreenCanvas drawing backend
+mod offscreen;
+// Module for tabulated (user-supplied) potentials
+mod tab;
+// Module for seedable random numbers
+mod rng;
+// Module for stepwise (streaming) simulation
+mod stream;
+// Module for spectral analysis
+mod spectrum;
+// Module for the shared error type
+mod error;
+// Module for the floating-point types of the integrator
+mod float;
+// Module for native (non-wasm) use of the simulation, e.g. benchmarks
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
 
-// Re-export the SimulationParameters struct to be used from JavaScript
+use error::SimError;
+
+// Re-export the structs to be used from JavaScript
 pub use sim::SimulationParameters;
+pub use plt::{AxisStyle, PlotOptions};
+pub use stream::Simulation;
+
+// Start function run automatically when the WebAssembly module is initialized
+#[wasm_bindgen(start)]
+pub fn start() {
+    // Print panics (with Rust messages) to the browser console instead of `RuntimeError: unreachable`
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+}
 
 // Main simulation function called from JavaScript
 #[wasm_bindgen]
 pub fn simulate_and_plot(
     params: SimulationParameters,
     energy_canvas_id: &str,
-    displacement_canvas_id: &str
+    displacement_canvas_id: &str,
+    plot_options: Option<PlotOptions>
 ) -> Result<JsValue, JsValue> {
-    // 1. Run simulation based on parameters
+    // 1. Start a new render generation (earlier in-flight renders become stale) and run simulation based on parameters
+    let token = plt::RenderToken::begin();
     let result = sim::simulate_molecule(&params)?;
+    let plot_options = plot_options.unwrap_or_default();
     
-    // 2. Render energy plot
-    plt::render_energy_plot(&result, energy_canvas_id)?;
+    // 2. Render the energy and displacement plots (plot errors are returned with the data instead of failing the call)
+    let plot_errors = plt::draw_plots(
+        plt::canvas_backend(energy_canvas_id, &plot_options).map_err(|e| error_message(&e)),
+        plt::canvas_backend(displacement_canvas_id, &plot_options).map_err(|e| error_message(&e)),
+        displacement_plot_inputs(&params, &plot_options),
+        &result, &params, &plot_options, &token
+    );
+    
+    // 3. Return simulation data to JavaScript for animation
+    plotted_result_value(&PlottedResult { result: &result, plot_errors })
+}
+
+// Variant of simulate_and_plot drawing on OffscreenCanvas objects (e.g. transferred to a Web Worker)
+// instead of canvases looked up by ID in the document
+#[wasm_bindgen]
+pub fn simulate_and_plot_offscreen(
+    params: SimulationParameters,
+    energy_canvas: web_sys::OffscreenCanvas,
+    displacement_canvas: web_sys::OffscreenCanvas,
+    plot_options: Option<PlotOptions>
+) -> Result<JsValue, JsValue> {
+    // 1. Start a new render generation (earlier in-flight renders become stale) and run simulation based on parameters
+    let token = plt::RenderToken::begin();
+    let result = sim::simulate_molecule(&params)?;
+    let plot_options = plot_options.unwrap_or_default();
     
-    // 3. Render displacement plot
-    plt::render_displacement_plot(&result, displacement_canvas_id)?;
+    // 2. Render the energy and displacement plots on the OffscreenCanvas objects (plot errors are returned with the data)
+    let plot_errors = plt::draw_plots(
+        offscreen::OffscreenCanvasBackend::new(energy_canvas).map_err(|e| e.to_string()),
+        offscreen::OffscreenCanvasBackend::new(displacement_canvas).map_err(|e| e.to_string()),
+        displacement_plot_inputs(&params, &plot_options),
+        &result, &params, &plot_options, &token
+    );
     
-    // 4. Return simulation data to JavaScript for animation
+    // 3. Return simulation data to JavaScript for animation
+    plotted_result_value(&PlottedResult { result: &result, plot_errors })
+}
+
+// Structure returned by simulate_and_plot: the simulation result (same fields) and the messages of any
+// plots that failed, so a failed plot does not lose the data
+#[derive(Serialize)]
+struct PlottedResult<'a> {
+    #[serde(flatten)]
+    result: &'a sim::SimulationResult,
+    plot_errors: Vec<String>,
+}
+
+// Helper function to convert a PlottedResult to a JavaScript object (the flattened result fields would
+// otherwise make serde_wasm_bindgen return a Map)
+fn plotted_result_value(plotted: &PlottedResult) -> Result<JsValue, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    Ok(plotted.serialize(&serializer)?)
+}
+
+// Helper function to get the message of an error value (errors of this crate are strings)
+fn error_message(error: &JsValue) -> String {
+    error.as_string().unwrap_or_else(|| format!("{:?}", error))
+}
+
+// Function to run the simulation and return the energy or displacement plot (`plot`) as SVG text
+// (e.g. for print-quality figures)
+#[cfg(feature = "svg")]
+#[wasm_bindgen]
+pub fn simulate_to_svg(
+    params: SimulationParameters,
+    plot: &str,
+    width: u32,
+    height: u32,
+    plot_options: Option<PlotOptions>
+) -> Result<String, JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    let plot_options = plot_options.unwrap_or_default();
+    let (equilibrium_distance, thermal_amplitude) = displacement_plot_inputs(&params, &plot_options)?;
+    Ok(plt::render_plot_svg(&result, &params, plot, width, height, &plot_options, equilibrium_distance, thermal_amplitude)?)
+}
+
+// Helper function to get the extra inputs of the displacement plot
+// (the percent unit needs the equilibrium distance, the envelope the thermal amplitude)
+fn displacement_plot_inputs(params: &SimulationParameters, plot_options: &PlotOptions) -> Result<(Option<f64>, Option<f64>), SimError> {
+    let equilibrium_distance = if plot_options.displacement_unit() == "percent" {
+        Some(sim::equilibrium_distance(params)?)
+    } else {
+        None
+    };
+    let thermal_amplitude = if plot_options.show_thermal_envelope() {
+        Some(sim::thermal_amplitude(params)?)
+    } else {
+        None
+    };
+    Ok((equilibrium_distance, thermal_amplitude))
+}
+
+// Function to mark all in-flight simulate_and_plot renders stale (they finish without presenting)
+#[wasm_bindgen]
+pub fn cancel_pending() {
+    plt::cancel_pending();
+}
+
+// Simulation-only function returning the results (never touches the DOM, e.g. for Node.js scripts)
+#[wasm_bindgen]
+pub fn simulate(params: SimulationParameters) -> Result<JsValue, JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    Ok(to_value(&result)?)
+}
+
+// Function to continue a previous simulation result (as returned to JavaScript) for the duration of `params`
+// (returns only the new part, whose times continue from the previous final time)
+#[wasm_bindgen]
+pub fn simulate_continue(prev: JsValue, params: SimulationParameters) -> Result<JsValue, JsValue> {
+    let prev: sim::SimulationResult = from_value(prev)?;
+    let result = sim::simulate_continue(&prev, &params)?;
     Ok(to_value(&result)?)
 }
+
+// Function to get the starting configuration (displacement, velocity, force, energies) of the selected model
+#[wasm_bindgen]
+pub fn initial_state(params: SimulationParameters) -> Result<JsValue, JsValue> {
+    let state = sim::initial_state(&params)?;
+    Ok(to_value(&state)?)
+}
+
+// Simulation-only function returning the results as a JSON string
+#[wasm_bindgen]
+pub fn simulate_to_json(params: SimulationParameters) -> Result<String, JsValue> {
+    // Run simulation based on parameters
+    let result = sim::simulate_molecule(&params)?;
+
+    // Return simulation data as a JSON string
+    Ok(result.to_json()?)
+}
+
+// Simulation-only function returning only the named result fields (e.g. ["times", "displacements"]) as a JSON string
+#[wasm_bindgen]
+pub fn simulate_to_json_fields(params: SimulationParameters, fields: Vec<String>) -> Result<String, JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    Ok(result.to_json_fields(&fields)?)
+}
+
+// Simulation-only function returning the series as one flat, column-major Float64Array
+// (returns `{ data, layout }`, where `layout` gives the row count and the column order)
+#[wasm_bindgen]
+pub fn simulate_to_columns(params: SimulationParameters) -> Result<JsValue, JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    let data = js_sys::Float64Array::from(result.to_columns().as_slice());
+    
+    let output = js_sys::Object::new();
+    js_sys::Reflect::set(&output, &JsValue::from_str("data"), &data)?;
+    js_sys::Reflect::set(&output, &JsValue::from_str("layout"), &to_value(&result.column_layout())?)?;
+    Ok(output.into())
+}
+
+// Simulation-only function returning scalar statistics instead of the (large) time series
+#[wasm_bindgen]
+pub fn simulate_summary(params: SimulationParameters) -> Result<JsValue, JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    Ok(to_value(&result.summary())?)
+}
+
+// Function to interpolate the displacement, velocity and energies of a returned simulation result at `time`
+// (`result` is an object returned by e.g. simulate_and_plot; times outside the run are clamped)
+#[wasm_bindgen]
+pub fn sample_at(result: JsValue, time: f64) -> Result<JsValue, JsValue> {
+    let result: sim::SimulationResult = from_value(result)?;
+    Ok(to_value(&result.sample_at(time)?)?)
+}
+
+// Function to count the full oscillations (pairs of velocity sign changes) of a returned simulation result
+#[wasm_bindgen]
+pub fn oscillation_count(result: JsValue) -> Result<u32, JsValue> {
+    let result: sim::SimulationResult = from_value(result)?;
+    Ok(result.oscillation_count())
+}
+
+// Function to find when the amplitude envelope of a returned simulation result decays to 1/e of its
+// initial value (e.g. while a temperature ramp cools the run; undefined if it never does)
+#[wasm_bindgen]
+pub fn relaxation_time(result: JsValue) -> Result<Option<f64>, JsValue> {
+    let result: sim::SimulationResult = from_value(result)?;
+    Ok(result.relaxation_time())
+}
+
+// Function to get the ratio KE / PE at each sample of a returned simulation result
+// (NaN where the potential energy is zero, at the potential minimum)
+#[wasm_bindgen]
+pub fn ke_pe_ratio(result: JsValue) -> Result<Vec<f64>, JsValue> {
+    let result: sim::SimulationResult = from_value(result)?;
+    Ok(result.ke_pe_ratio())
+}
+
+// Function to check the integrator is time-reversible: the displacement error (Bohr) after running
+// forward and then back with the velocity reversed
+#[wasm_bindgen]
+pub fn time_reverse_check(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::time_reverse_check(&params)?)
+}
+
+// Function to measure the oscillation period for the given parameters
+#[wasm_bindgen]
+pub fn measure_period(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::measure_period(&params)?)
+}
+
+// Function to compute the classical action ∮ p dq (atomic units) over one oscillation period
+#[wasm_bindgen]
+pub fn classical_action(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::classical_action(&params)?)
+}
+
+// Function to estimate the anharmonicity (fractional frequency drop with amplitude) of a non-harmonic model
+#[wasm_bindgen]
+pub fn anharmonicity(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::anharmonicity(&params)?)
+}
+
+// Function to average the energies over `n_runs` random thermal trajectories (seeded from `seed`)
+#[wasm_bindgen]
+pub fn ensemble_average(params: SimulationParameters, n_runs: usize, seed: u64) -> Result<JsValue, JsValue> {
+    let ensemble = sim::ensemble_average(&params, n_runs, seed)?;
+    Ok(to_value(&ensemble)?)
+}
+
+// Function to compute the normalized displacement autocorrelation (uniform sampling only)
+#[wasm_bindgen]
+pub fn autocorrelation(params: SimulationParameters) -> Result<JsValue, JsValue> {
+    let correlation = sim::autocorrelation(&params)?;
+    Ok(to_value(&correlation)?)
+}
+
+// Function to compute the power spectrum of the displacements (uniform sampling only)
+#[wasm_bindgen]
+pub fn power_spectrum(params: SimulationParameters) -> Result<JsValue, JsValue> {
+    if params.sampling() != "uniform" {
+        return Err(SimError::InvalidInput(String::from("Power spectrum needs uniformly sampled results")).into());
+    }
+    let result = sim::simulate_molecule(&params)?;
+    let spectrum = spectrum::power_spectrum(&result)?;
+    Ok(to_value(&spectrum)?)
+}
+
+// Function to measure the energy drift at each timestep (e.g. to show the O(dt²) scaling of Verlet)
+#[wasm_bindgen]
+pub fn timestep_convergence(params: SimulationParameters, timesteps: Vec<f64>) -> Result<JsValue, JsValue> {
+    let drifts = sim::timestep_convergence(&params, &timesteps)?;
+    Ok(to_value(&drifts)?)
+}
+
+// Function to estimate the largest timestep integrating the model stably at the temperature
+// (1/20 of the fastest local oscillation period along the trajectory)
+#[wasm_bindgen]
+pub fn max_stable_timestep(element: &str, model: &str, temperature: f64) -> Result<f64, JsValue> {
+    Ok(sim::max_stable_timestep(element, model, temperature)?)
+}
+
+// Function to run the simulation at each temperature and return (temperature, peak displacement) pairs
+#[wasm_bindgen]
+pub fn amplitude_vs_temperature(
+    element: &str,
+    model: &str,
+    temperatures: Vec<f64>,
+    duration: f64,
+    timestep: f64
+) -> Result<JsValue, JsValue> {
+    let amplitudes = sim::amplitude_vs_temperature(element, model, &temperatures, duration, timestep)?;
+    Ok(to_value(&amplitudes)?)
+}
+
+// Function to get the timestep that resolves the small-oscillation period with `steps_per_period` steps
+#[wasm_bindgen]
+pub fn timestep_for_resolution(element: &str, model: &str, steps_per_period: f64) -> Result<f64, JsValue> {
+    Ok(sim::timestep_for_resolution(element, model, steps_per_period)?)
+}
+
+// Function to get the potential well depth (infinite for the harmonic oscillator)
+#[wasm_bindgen]
+pub fn well_depth(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::well_depth(&params)?)
+}
+
+// Function to get the equilibrium interatomic distance of the selected element
+#[wasm_bindgen]
+pub fn equilibrium_distance(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::equilibrium_distance(&params)?)
+}
+
+// Function to check whether the initial state is bound (total energy below the well depth)
+#[wasm_bindgen]
+pub fn is_bound(params: SimulationParameters) -> Result<bool, JsValue> {
+    Ok(sim::is_bound(&params)?)
+}
+
+// Function to estimate the dissociated fraction exp(-D / k_B T) of an element with Morse parameters
+#[wasm_bindgen]
+pub fn dissociation_fraction(element: &str, temperature: f64) -> Result<f64, JsValue> {
+    Ok(sim::dissociation_fraction(element, temperature)?)
+}
+
+// Function to get the equipartition energy ½ k_B T per degree of freedom ("au" or "si" `unit`)
+#[wasm_bindgen]
+pub fn equipartition_energy(temperature: f64, unit: &str) -> Result<f64, JsValue> {
+    Ok(sim::equipartition_energy(temperature, unit)?)
+}
+
+// Function to get the harmonic vibrational wavenumber (cm⁻¹) of the harmonic and Morse models
+#[wasm_bindgen]
+pub fn vibrational_wavenumber(params: SimulationParameters) -> Result<f64, JsValue> {
+    Ok(sim::vibrational_wavenumber(&params)?)
+}
+
+// Function to sample the force of the selected model over [r_min, r_max] as (r, F) pairs
+#[wasm_bindgen]
+pub fn force_curve(params: SimulationParameters, r_min: f64, r_max: f64, n_points: usize) -> Result<JsValue, JsValue> {
+    let curve = sim::force_curve(&params, r_min, r_max, n_points)?;
+    Ok(to_value(&curve)?)
+}
+
+// Function to find the classical turning points for the given parameters
+#[wasm_bindgen]
+pub fn turning_points(params: SimulationParameters) -> Result<Vec<f64>, JsValue> {
+    Ok(sim::turning_points(&params)?)
+}
+
+// Function to simulate a tabulated potential V(r) (atomic units) with the given reduced mass
+#[wasm_bindgen]
+pub fn simulate_tabulated(
+    r_values: Vec<f64>,
+    v_values: Vec<f64>,
+    mass_au: f64,
+    params: SimulationParameters
+) -> Result<JsValue, JsValue> {
+    let result = tab::simulate_tabulated(r_values, v_values, mass_au, &params)?;
+    Ok(to_value(&result)?)
+}
+
+// Function to get descriptive metadata (category, suitable models) for an element
+#[wasm_bindgen]
+pub fn element_info(symbol: &str) -> Result<JsValue, JsValue> {
+    let info = sim::get_element_info(symbol)?;
+    Ok(to_value(info)?)
+}
+
+// Function to check whether two elements form a diatomic the table has parameters for (homonuclear only)
+#[wasm_bindgen]
+pub fn is_supported_pair(a: &str, b: &str) -> bool {
+    sim::is_supported_pair(a, b)
+}
+
+// Function to check whether a model shows anharmonic effects (false for "harmonic" and unknown models)
+#[wasm_bindgen]
+pub fn is_anharmonic(model: &str) -> bool {
+    sim::is_anharmonic(model)
+}
+
+// Function to get all the physical constants (masses, force constants, ...) of an element
+#[wasm_bindgen]
+pub fn get_element_properties_js(symbol: &str) -> Result<JsValue, JsValue> {
+    let constants = sim::get_element_constants(symbol)?;
+    Ok(to_value(&constants)?)
+}
+
+// Function to run an element's potential with each substituted mass (atomic units), e.g. H2 / D2 / T2
+#[wasm_bindgen]
+pub fn isotope_comparison(
+    element: &str,
+    masses_au: Vec<f64>,
+    duration: f64,
+    timestep: f64,
+    temperature: f64
+) -> Result<JsValue, JsValue> {
+    let runs = sim::isotope_comparison(element, &masses_au, duration, timestep, temperature)?;
+    Ok(to_value(&runs)?)
+}
+
+// Function to convert spectroscopic constants ω_e and ω_e x_e (cm⁻¹) to Morse parameters (atomic units)
+// for a diatomic with (reduced) mass `mass_au`
+#[wasm_bindgen]
+pub fn morse_from_spectroscopic(omega_e: f64, omega_e_x_e: f64, mass_au: f64) -> Result<JsValue, JsValue> {
+    let parameters = sim::morse_from_spectroscopic(omega_e, omega_e_x_e, mass_au)?;
+    Ok(to_value(&parameters)?)
+}
+
+// Function to run the Morse model of the selected element with parameters from spectroscopic constants (cm⁻¹)
+#[wasm_bindgen]
+pub fn simulate_morse_spectroscopic(params: SimulationParameters, omega_e: f64, omega_e_x_e: f64) -> Result<JsValue, JsValue> {
+    let result = sim::simulate_morse_spectroscopic(&params, omega_e, omega_e_x_e)?;
+    Ok(to_value(&result)?)
+}
+
+// Function to get illustrative Lennard-Jones parameters (eps, r*) for an arbitrary mass (atomic units),
+// interpolated between the tabulated elements
+#[wasm_bindgen]
+pub fn interpolate_lennard_jones(mass_au: f64) -> Result<JsValue, JsValue> {
+    let parameters = sim::interpolate_lennard_jones(mass_au)?;
+    Ok(to_value(&parameters)?)
+}
+
+// Function to simulate and plot the kinetic against the potential energy on one canvas
+#[wasm_bindgen]
+pub fn render_energy_exchange_plot(
+    params: SimulationParameters,
+    canvas_id: &str,
+    plot_options: Option<PlotOptions>
+) -> Result<(), JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    plt::render_energy_exchange_plot(&result, &params, canvas_id, &plot_options.unwrap_or_default())
+}
+
+// Function to simulate and plot the displacement (left axis) and total energy (right axis) on one canvas
+#[wasm_bindgen]
+pub fn render_displacement_energy_dual(
+    params: SimulationParameters,
+    canvas_id: &str,
+    plot_options: Option<PlotOptions>
+) -> Result<(), JsValue> {
+    let result = sim::simulate_molecule(&params)?;
+    plt::render_displacement_energy_dual(&result, &params, canvas_id, &plot_options.unwrap_or_default())
+}
+
+// Function to overlay the harmonic and Morse displacements of an element on one canvas
+// (only for elements with parameters for both models, e.g. H)
+#[wasm_bindgen]
+pub fn render_model_comparison_plot(
+    element: &str,
+    temperature: f64,
+    canvas_id: &str,
+    plot_options: Option<PlotOptions>
+) -> Result<(), JsValue> {
+    let runs = sim::model_comparison(element, temperature)?;
+    let caption = format!("Harmonic vs Morse ({})", element);
+    plt::render_comparison_plot(&runs, &caption, canvas_id, &plot_options.unwrap_or_default())
+}
+
+// Function to overlay the harmonic potentials V(r) = ½ k r² of several elements on one canvas
+// (for comparing bond stiffness, e.g. H against Ar)
+#[wasm_bindgen]
+pub fn render_potentials_comparison(
+    elements: Vec<String>,
+    canvas_id: &str,
+    plot_options: Option<PlotOptions>
+) -> Result<(), JsValue> {
+    let curves = sim::harmonic_potential_curves(&elements)?;
+    plt::render_potentials_comparison(&curves, canvas_id, &plot_options.unwrap_or_default())
+}
+
+// Function to plot the residual x_numeric(t) - x_analytic(t) of a harmonic run (the accumulating integration error)
+#[wasm_bindgen]
+pub fn render_residual_plot(
+    params: SimulationParameters,
+    canvas_id: &str,
+    plot_options: Option<PlotOptions>
+) -> Result<(), JsValue> {
+    let residuals = sim::harmonic_residuals(&params)?;
+    plt::render_residual_plot(&residuals, &params, canvas_id, &plot_options.unwrap_or_default())
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    // Plot errors: a plot that fails to draw, or whose inputs fail, is reported while the other plot is still drawn
+    #[test]
+    #[cfg(feature = "svg")]
+    fn failed_plot_skips_only_that_plot() {
+        use plotters::prelude::SVGBackend;
+        let token = plt::RenderToken::begin();
+        let (mut energy_svg, mut displacement_svg) = (String::new(), String::new());
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let mut result = sim::simulate_molecule(&params).unwrap();
+        result.total_energies[1] = f64::INFINITY;
+        let plot_errors = plt::draw_plots(
+            Ok(SVGBackend::with_string(&mut energy_svg, (800, 400))),
+            Ok(SVGBackend::with_string(&mut displacement_svg, (800, 400))),
+            displacement_plot_inputs(&params, &PlotOptions::default()),
+            &result, &params, &PlotOptions::default(), &token
+        );
+        assert_eq!(plot_errors.len(), 1);
+        assert!(plot_errors[0].starts_with("Cannot plot the energy axis over a non-finite range"), "{}", plot_errors[0]);
+        assert!(displacement_svg.contains("<polyline"));
+
+        // The thermal envelope has no amplitude for a Morse run, so only the displacement plot is skipped
+        let (mut energy_svg, mut displacement_svg) = (String::new(), String::new());
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_show_thermal_envelope(true);
+        let plot_errors = plt::draw_plots(
+            Ok(SVGBackend::with_string(&mut energy_svg, (800, 400))),
+            Ok(SVGBackend::with_string(&mut displacement_svg, (800, 400))),
+            displacement_plot_inputs(&params, &options),
+            &result, &params, &options, &token
+        );
+        assert_eq!(plot_errors, vec![sim::thermal_amplitude(&params).unwrap_err().to_string()]);
+        assert!(energy_svg.contains("<polyline"));
+        assert!(!displacement_svg.contains("<polyline"));
+
+        // The returned object carries the result fields next to the plot errors
+        let json = serde_json::to_string(&PlottedResult { result: &result, plot_errors }).unwrap();
+        assert!(json.contains("\"displacements\":["));
+        assert!(json.contains("\"plot_errors\":[\""));
+    }
+
+    // Start: installing the panic hook succeeds, also when the module is initialized again
+    #[test]
+    fn start_installs_the_panic_hook() {
+        start();
+        start();
+    }
+}
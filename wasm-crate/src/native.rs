@@ -0,0 +1,24 @@
+/*
+Module for using the simulation natively (outside the browser), e.g. for benchmarks and tests
+
+Contains:
+ - Re-exports of the simulation API without JsValue:
+    - SimulationParameters, SimulationState and SimulationResult (and the other result structs)
+    - simulate_molecule, initial_state and the analysis functions of the sim module
+    - simulate_tabulated and power_spectrum
+    - SimError (every function returns Result<_, SimError>)
+ - Only compiled for non-wasm targets; unseeded randomized initial conditions and the timing read the system clock
+*/
+
+pub use crate::error::SimError;
+pub use crate::sim::{
+    SimulationParameters, SimulationState, SimulationResult, EnergySeries, SimulationSummary, SampledState, ColumnLayout,
+    EnsembleResult, Correlation, IsotopeResult, LennardJonesParameters, MorseParameters, ElementConstants, ElementInfo,
+    simulate_molecule, simulate_continue, initial_state, get_element_constants, get_element_info, is_supported_pair, is_anharmonic,
+    measure_period, classical_action, time_reverse_check, timestep_for_resolution, max_stable_timestep, anharmonicity, ensemble_average, autocorrelation, timestep_convergence,
+    amplitude_vs_temperature, isotope_comparison, interpolate_lennard_jones, model_comparison, morse_from_spectroscopic, simulate_morse_spectroscopic,
+    well_depth, is_bound, dissociation_fraction, equipartition_energy, equilibrium_distance, thermal_amplitude, vibrational_wavenumber,
+    force_curve, turning_points, harmonic_residuals, harmonic_potential_curves, PotentialCurve, ComparisonRun,
+};
+pub use crate::spectrum::{Spectrum, power_spectrum};
+pub use crate::tab::simulate_tabulated;
@@ -0,0 +1,277 @@
+/*
+Module for drawing plots on an OffscreenCanvas (e.g. inside a Web Worker, where there is no DOM)
+
+Contains:
+ - OffscreenCanvasBackend struct:
+    - Plotters drawing backend for the 2D context of an OffscreenCanvas (follows the CanvasBackend of plotters-canvas)
+    - measures text with the context itself, since Plotters' default text measurement needs the document
+ - OffscreenCanvasError struct:
+    - error type of the backend (JavaScript exceptions as strings)
+*/
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{OffscreenCanvas, OffscreenCanvasRenderingContext2d};
+use plotters::prelude::DrawingBackend;
+use plotters_backend::text_anchor::{HPos, VPos};
+use plotters_backend::{BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingErrorKind, FontTransform};
+
+use crate::error::SimError;
+
+// Drawing backend for the 2D context of an OffscreenCanvas
+pub struct OffscreenCanvasBackend {
+    canvas: OffscreenCanvas,
+    context: OffscreenCanvasRenderingContext2d,
+}
+
+// Define error struct of the backend (message of the JavaScript exception)
+#[derive(Debug)]
+pub struct OffscreenCanvasError(String);
+
+impl std::fmt::Display for OffscreenCanvasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Offscreen canvas error: {}", self.0)
+    }
+}
+
+impl std::error::Error for OffscreenCanvasError {}
+
+// Helper function to turn a JavaScript exception into a drawing error
+fn drawing_error(error: JsValue) -> DrawingErrorKind<OffscreenCanvasError> {
+    let message = js_sys::JSON::stringify(&error)
+        .map(String::from)
+        .unwrap_or_else(|_| String::from("Unknown"));
+    DrawingErrorKind::DrawingError(OffscreenCanvasError(message))
+}
+
+// Helper function to get the CSS color of a backend color
+fn css_color(color: BackendColor) -> String {
+    let (r, g, b) = color.rgb;
+    format!("rgba({},{},{},{})", r, g, b, color.alpha)
+}
+
+impl OffscreenCanvasBackend {
+    pub fn new(canvas: OffscreenCanvas) -> Result<OffscreenCanvasBackend, SimError> {
+        // Get the 2D context of the canvas
+        let context = canvas.get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<OffscreenCanvasRenderingContext2d>().ok())
+            .ok_or_else(|| SimError::Plot(String::from("Cannot get the 2D context of the offscreen canvas")))?;
+        Ok(OffscreenCanvasBackend { canvas, context })
+    }
+
+    // Sets the stroke color and line width
+    fn set_line_style(&mut self, style: &impl BackendStyle) {
+        self.context.set_stroke_style_str(&css_color(style.color()));
+        self.context.set_line_width(style.stroke_width() as f64);
+    }
+
+    // Sets the font of the context from a text style
+    fn set_font(&self, style: &impl BackendTextStyle) {
+        self.context.set_font(&format!("{} {}px {}", style.style().as_str(), style.size(), style.family().as_str()));
+    }
+}
+
+impl DrawingBackend for OffscreenCanvasBackend {
+    type ErrorType = OffscreenCanvasError;
+
+    fn get_size(&self) -> (u32, u32) {
+        (self.canvas.width(), self.canvas.height())
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        Ok(())
+    }
+
+    fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        if color.alpha == 0.0 {
+            return Ok(());
+        }
+        self.context.set_fill_style_str(&css_color(color));
+        self.context.fill_rect(point.0 as f64, point.1 as f64, 1.0, 1.0);
+        Ok(())
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S
+    ) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        if style.color().alpha == 0.0 {
+            return Ok(());
+        }
+        // (horizontal and vertical lines are moved half a pixel so they are not blurred over two pixels)
+        let (mut from, mut to) = ((from.0 as f64, from.1 as f64), (to.0 as f64, to.1 as f64));
+        if from.0 == to.0 {
+            from.0 -= 0.5;
+            to.0 -= 0.5;
+        }
+        if from.1 == to.1 {
+            from.1 -= 0.5;
+            to.1 -= 0.5;
+        }
+        self.set_line_style(style);
+        self.context.begin_path();
+        self.context.move_to(from.0, from.1);
+        self.context.line_to(to.0, to.1);
+        self.context.stroke();
+        Ok(())
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool
+    ) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        if style.color().alpha == 0.0 {
+            return Ok(());
+        }
+        let (x, y) = (upper_left.0 as f64, upper_left.1 as f64);
+        let (width, height) = ((bottom_right.0 - upper_left.0) as f64, (bottom_right.1 - upper_left.1) as f64);
+        if fill {
+            self.context.set_fill_style_str(&css_color(style.color()));
+            self.context.fill_rect(x, y, width, height);
+        } else {
+            self.set_line_style(style);
+            self.context.stroke_rect(x, y, width, height);
+        }
+        Ok(())
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S
+    ) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        if style.color().alpha == 0.0 {
+            return Ok(());
+        }
+        let mut path = path.into_iter();
+        self.context.begin_path();
+        if let Some(start) = path.next() {
+            self.set_line_style(style);
+            self.context.move_to(start.0 as f64, start.1 as f64);
+            for next in path {
+                self.context.line_to(next.0 as f64, next.1 as f64);
+            }
+        }
+        self.context.stroke();
+        Ok(())
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S
+    ) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        if style.color().alpha == 0.0 {
+            return Ok(());
+        }
+        let mut path = path.into_iter();
+        self.context.begin_path();
+        if let Some(start) = path.next() {
+            self.context.set_fill_style_str(&css_color(style.color()));
+            self.context.move_to(start.0 as f64, start.1 as f64);
+            for next in path {
+                self.context.line_to(next.0 as f64, next.1 as f64);
+            }
+            self.context.close_path();
+        }
+        self.context.fill();
+        Ok(())
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool
+    ) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        if style.color().alpha == 0.0 {
+            return Ok(());
+        }
+        if fill {
+            self.context.set_fill_style_str(&css_color(style.color()));
+        } else {
+            self.set_line_style(style);
+        }
+        self.context.begin_path();
+        self.context.arc(center.0 as f64, center.1 as f64, radius as f64, 0.0, 2.0 * std::f64::consts::PI)
+            .map_err(drawing_error)?;
+        if fill {
+            self.context.fill();
+        } else {
+            self.context.stroke();
+        }
+        Ok(())
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle
+    ) -> Result<(u32, u32), DrawingErrorKind<OffscreenCanvasError>> {
+        // Measure with the context (the font height falls back to the font size if not reported)
+        self.set_font(style);
+        let metrics = self.context.measure_text(text).map_err(drawing_error)?;
+        let height = metrics.font_bounding_box_ascent() + metrics.font_bounding_box_descent();
+        let height = if height.is_finite() && height > 0.0 { height } else { style.size() };
+        Ok((metrics.width().ceil() as u32, height.ceil() as u32))
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord
+    ) -> Result<(), DrawingErrorKind<OffscreenCanvasError>> {
+        let color = style.color();
+        if color.alpha == 0.0 {
+            return Ok(());
+        }
+
+        // Rotate about the anchor point if needed
+        let angle = match style.transform() {
+            FontTransform::None => 0.0,
+            FontTransform::Rotate90 => 0.5 * std::f64::consts::PI,
+            FontTransform::Rotate180 => std::f64::consts::PI,
+            FontTransform::Rotate270 => 1.5 * std::f64::consts::PI,
+        };
+        let (mut x, mut y) = (pos.0 as f64, pos.1 as f64);
+        if angle != 0.0 {
+            self.context.save();
+            self.context.translate(x, y).map_err(drawing_error)?;
+            self.context.rotate(angle).map_err(drawing_error)?;
+            x = 0.0;
+            y = 0.0;
+        }
+
+        // Align the text to its anchor and draw it
+        self.context.set_text_baseline(match style.anchor().v_pos {
+            VPos::Top => "top",
+            VPos::Center => "middle",
+            VPos::Bottom => "bottom",
+        });
+        self.context.set_text_align(match style.anchor().h_pos {
+            HPos::Left => "start",
+            HPos::Right => "end",
+            HPos::Center => "center",
+        });
+        self.context.set_fill_style_str(&css_color(color));
+        self.set_font(style);
+        self.context.fill_text(text, x, y).map_err(drawing_error)?;
+
+        if angle != 0.0 {
+            self.context.restore();
+        }
+        Ok(())
+    }
+}
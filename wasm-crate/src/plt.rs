@@ -2,174 +2,2325 @@
 Module for rendering plots of simulation results using Plotters and WebAssembly.
 
 Contains:
+ - PlotOptions struct:
+    - optional settings for the plots, such as the energy and displacement units, supersampling factor and axis style
+    - x-axis of the energy and displacement plots in time or in integration steps
+    - optional fixed y-ranges of the energy and displacement plots (to compare runs on the same axes)
+    - colors of the elements on the overlay plots (built-in per element, overridable)
+ - AxisStyle struct:
+    - colors (CSS hex strings) for the axis lines, grid lines, axis text and caption
  - Functions:
-    - render_energy_plot: renders a plot of potential, kinetic, and total energy over time
-        - Takes a SimulationResult and a canvas ID
-        - Uses Plotters to draw the energy data on a specified HTML canvas
-    - render_displacement_plot: renders a plot of displacement over time
-        - Takes a SimulationResult and a canvas ID
-        - Uses Plotters to draw the displacement data on a specified HTML canvas
+    - plot_caption: fixed plot title, or a caption generated from the simulation parameters
+    - x_axis_values: time or step index for the x-axis of the energy and displacement plots
+    - x_axis_end: end of that x-axis (one timestep for a single-sample run, instead of zero width)
+    - x_label_count: number of x-axis labels from the canvas width (unless set in PlotOptions)
+    - format_axis_label: formats axis labels (scientific notation for very small or large values)
+    - check_axis_range: reports a non-finite axis range (e.g. a diverged run) as a plot error
+    - check_atomic_units: reports results in SI units (plots are scaled and labelled in atomic units)
+    - fixed_axis_range: replaces the autoscaled ends of an axis by the fixed ones from PlotOptions
+    - RenderToken struct / cancel_pending function:
+        - render generations, so a newer simulate_and_plot call marks older in-flight renders stale
+        - stale renders skip presenting their drawing
+    - canvas_backend: looks up a canvas by ID and creates a drawing backend for it (or, for an
+      OffscreenCanvas, OffscreenCanvasBackend::new)
+        - Reports a readable error if the element is missing or not a canvas
+        - Supersampling renders at N times the resolution, keeping the displayed (CSS) size
+    - draw_plots: draws the energy and displacement plots of a run on any drawing backends
+        - A plot whose backend or inputs failed, or that fails to draw, is skipped (its message is
+          returned) while the other plot is still drawn
+    - draw_energy_plot: draws a plot of potential, kinetic, and total energy over time
+        - Takes a SimulationResult, its SimulationParameters (for the caption) and PlotOptions
+        - Scales energies to the selected unit (labels include the unit when not atomic units)
+        - Each of the potential, kinetic and total energy series can be hidden
+        - Each series is drawn solid, dashed or dotted (the legend shows the same style)
+        - The caption can include the relative energy drift of the run (show_energy_drift)
+        - Optionally stacks the kinetic on the potential energy as filled areas (the top is the total energy;
+          a hidden potential or kinetic energy is left out of the stack)
+        - The energy axis can start at zero when no energy is negative (clamp_nonnegative)
+        - Uses Plotters to draw the energy data on any backend (canvas, OffscreenCanvas or SVG)
+    - draw_displacement_plot: draws a plot of displacement over time
+        - Takes a SimulationResult, its SimulationParameters (for the caption) and PlotOptions
+        - Optionally fills the area between the curve and the equilibrium line
+        - Optionally also draws the interatomic distances (with a legend)
+        - Lengths in Bohr or as a percentage of the equilibrium distance
+        - Optionally draws the thermal envelope ±sqrt(2 k_B T / k) as dashed lines
+        - Optionally marks the turning points (velocity sign changes) with circles
+        - Uses Plotters to draw the displacement data on any backend (canvas, OffscreenCanvas or SVG)
+    - render_plot_svg (feature "svg"): renders the energy or displacement plot as SVG markup
+    - render_energy_exchange_plot: renders kinetic against potential energy (parametric in time)
+        - Includes the line KE + PE = E0 of exact energy conservation
+    - render_displacement_energy_dual: renders displacement and total energy over time on one chart
+        - Displacement on the left and energy on the right y-axis, sharing the x-axis (time or steps)
+    - render_comparison_plot: renders the displacements of several labelled runs on one plot
+        - Each run gets its own color and legend entry (e.g. harmonic vs Morse)
+        - The x-axis shows time or steps, as the other time series plots
+    - render_potentials_comparison: renders the harmonic potentials of several elements on one plot
+        - One color and legend entry per element, energies in the selected unit
+        - Each element keeps its color (see PlotOptions::element_color) whatever else is plotted
+    - render_residual_plot: renders the residuals of a harmonic run against the analytic solution over time
+ - tests module (native only):
+    - render token staleness
+    - SVG markup of the energy plot
+    - potentials comparison of two elements (as SVG)
+    - displacement plot with a fixed y-range (as SVG)
+    - energy plot with dashed and dotted series (as SVG)
+    - energy drift in the energy plot caption (as SVG)
+    - single-sample run on the displacement and energy plots (as SVG)
+    - stacked energy areas (as SVG)
+    - element colors across two potentials comparisons, and overridden (as SVG)
+    - harmonic residual plot (as SVG)
+    - x-axis label count on narrow canvases
+    - energy axis range with clamp_nonnegative
+    - energy plot legend in eV (as SVG)
+    - energy plot supersampled at 2x (as SVG)
+    - energy and displacement plots with a custom axis palette (as SVG)
+    - displacement plot with a filled area (as SVG)
+    - displacement plot with the interatomic distances (as SVG)
+    - harmonic and Morse comparison of hydrogen (as SVG)
+    - displacement unit conversion, and the displacement plot in percent (as SVG)
+    - energy plot labels for energies around 1e-19 (as SVG)
+    - energy and displacement plots of a run at T = 1e-10 K (as SVG)
+    - energy exchange plot of a harmonic run (as SVG)
+    - energy plot with only the total energy (as SVG)
+    - displacement plot with the thermal envelope (as SVG)
+    - dual-axis displacement and energy plot (as SVG)
+    - energy and displacement plots with an automatic caption (as SVG)
+    - energy and displacement plots over the step index (as SVG)
+    - stacked energy areas of the shown series only (as SVG)
+    - comparison plot of single-sample runs, in steps and without runs (as SVG)
+ - wasm_tests module (browser only, wasm-pack test --headless):
+    - readable error for an element that is not a canvas
+    - energy and displacement plots drawn on an OffscreenCanvas
 */
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use wasm_bindgen::prelude::*;
 use web_sys::HtmlCanvasElement;
+use plotters::coord::types::RangedCoordf64;
+use plotters::coord::Shift;
+use plotters::element::{DashedPathElement, DottedPathElement};
 use plotters::prelude::*;
 use plotters_canvas::CanvasBackend;
 
-// Import the SimulationResult type (holds simulated data)
-use crate::sim::SimulationResult;
+// Import the SimulationResult type (holds simulated data), the parameters (for captions) and the shared error type
+use crate::error::SimError;
+use crate::sim::{ComparisonRun, PotentialCurve, SimulationParameters, SimulationResult};
 
-// Function to render the energy plot
-pub fn render_energy_plot(result: &SimulationResult, canvas_id: &str) -> Result<(), JsValue> {
-    // Get the canvas element
-    let document = web_sys::window().unwrap().document().unwrap();
+// Energy conversion factors (from Hartree)
+const HARTREE_TO_EV: f64 = 27.211386;
+const HARTREE_TO_KJ_PER_MOL: f64 = 2625.4996;
+
+// Define options struct for plot settings
+#[wasm_bindgen]
+pub struct PlotOptions {
+    energy_unit: String,  // Energy unit for the energy plot ("au", "eV", "kJ/mol")
+    supersampling: u32,   // Resolution multiplier of the backing canvas (1 = no supersampling)
+    axis_style: AxisStyle,// Colors of the axes, grid, text and caption
+    fill_displacement: bool, // Whether to fill the area between the displacement curve and equilibrium
+    show_distances: bool,    // Whether to also draw the interatomic distances on the displacement plot
+    displacement_unit: String, // Length unit of the displacement plot ("au", "percent" of the equilibrium distance)
+    significant_figures: u32,  // Significant figures of the axis labels (scientific notation, 0 = automatic)
+    show_potential: bool,      // Whether to draw the potential energy on the energy plot
+    show_kinetic: bool,        // Whether to draw the kinetic energy on the energy plot
+    show_total: bool,          // Whether to draw the total energy on the energy plot
+    show_thermal_envelope: bool, // Whether to draw the ±sqrt(2 k_B T / k) lines on the displacement plot (harmonic only)
+    auto_caption: bool,        // Whether to caption the plots with the simulation parameters instead of a fixed title
+    x_axis: String,            // Quantity on the x-axis of the energy and displacement plots ("time", "steps")
+    x_labels: u32,             // Number of x-axis labels (0 = one per 60 px of canvas width)
+    clamp_nonnegative: bool,   // Whether the energy axis starts at zero when no shown energy is negative
+    show_turning_points: bool, // Whether to mark the turning points (velocity sign changes) on the displacement plot
+    energy_y_min: Option<f64>,       // Fixed lower end of the energy axis (in the energy unit, None = autoscale)
+    energy_y_max: Option<f64>,       // Fixed upper end of the energy axis
+    displacement_y_min: Option<f64>, // Fixed lower end of the displacement axis (in the length unit, None = autoscale)
+    displacement_y_max: Option<f64>, // Fixed upper end of the displacement axis
+    potential_line_style: String,    // Line style of the potential energy ("solid", "dashed", "dotted")
+    kinetic_line_style: String,      // Line style of the kinetic energy
+    total_line_style: String,        // Line style of the total energy
+    show_energy_drift: bool,         // Whether to append the relative energy drift of the run to the energy plot caption
+    stacked_energies: bool,          // Whether to draw the kinetic energy stacked on the potential energy as filled areas
+    element_colors: Vec<(String, String)>, // Caller's (element, CSS hex color) pairs overriding the element colors of overlays
+}
+
+#[wasm_bindgen]
+impl PlotOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PlotOptions {
+        PlotOptions {
+            energy_unit: String::from("au"),
+            supersampling: 1,
+            axis_style: AxisStyle::new(),
+            fill_displacement: false,
+            show_distances: false,
+            displacement_unit: String::from("au"),
+            significant_figures: 0,
+            show_potential: true,
+            show_kinetic: true,
+            show_total: true,
+            show_thermal_envelope: false,
+            auto_caption: false,
+            x_axis: String::from("time"),
+            x_labels: 0,
+            clamp_nonnegative: false,
+            show_turning_points: false,
+            energy_y_min: None,
+            energy_y_max: None,
+            displacement_y_min: None,
+            displacement_y_max: None,
+            potential_line_style: String::from("solid"),
+            kinetic_line_style: String::from("solid"),
+            total_line_style: String::from("solid"),
+            show_energy_drift: false,
+            stacked_energies: false,
+            element_colors: Vec::new(),
+        }
+    }
+    
+    // Getters and setters for accessing the fields
+    #[wasm_bindgen(getter)]
+    pub fn energy_unit(&self) -> String {
+        self.energy_unit.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_energy_unit(&mut self, energy_unit: String) {
+        self.energy_unit = energy_unit;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn supersampling(&self) -> u32 {
+        self.supersampling
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_supersampling(&mut self, supersampling: u32) {
+        self.supersampling = supersampling;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn axis_style(&self) -> AxisStyle {
+        self.axis_style.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_axis_style(&mut self, axis_style: AxisStyle) {
+        self.axis_style = axis_style;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn fill_displacement(&self) -> bool {
+        self.fill_displacement
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_fill_displacement(&mut self, fill_displacement: bool) {
+        self.fill_displacement = fill_displacement;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_distances(&self) -> bool {
+        self.show_distances
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_distances(&mut self, show_distances: bool) {
+        self.show_distances = show_distances;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn displacement_unit(&self) -> String {
+        self.displacement_unit.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_displacement_unit(&mut self, displacement_unit: String) {
+        self.displacement_unit = displacement_unit;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn significant_figures(&self) -> u32 {
+        self.significant_figures
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_significant_figures(&mut self, significant_figures: u32) {
+        self.significant_figures = significant_figures;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_potential(&self) -> bool {
+        self.show_potential
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_potential(&mut self, show_potential: bool) {
+        self.show_potential = show_potential;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_kinetic(&self) -> bool {
+        self.show_kinetic
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_kinetic(&mut self, show_kinetic: bool) {
+        self.show_kinetic = show_kinetic;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_total(&self) -> bool {
+        self.show_total
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_total(&mut self, show_total: bool) {
+        self.show_total = show_total;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_thermal_envelope(&self) -> bool {
+        self.show_thermal_envelope
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_thermal_envelope(&mut self, show_thermal_envelope: bool) {
+        self.show_thermal_envelope = show_thermal_envelope;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn auto_caption(&self) -> bool {
+        self.auto_caption
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_auto_caption(&mut self, auto_caption: bool) {
+        self.auto_caption = auto_caption;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn x_axis(&self) -> String {
+        self.x_axis.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_x_axis(&mut self, x_axis: String) {
+        self.x_axis = x_axis;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn x_labels(&self) -> u32 {
+        self.x_labels
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_x_labels(&mut self, x_labels: u32) {
+        self.x_labels = x_labels;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn clamp_nonnegative(&self) -> bool {
+        self.clamp_nonnegative
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_clamp_nonnegative(&mut self, clamp_nonnegative: bool) {
+        self.clamp_nonnegative = clamp_nonnegative;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_turning_points(&self) -> bool {
+        self.show_turning_points
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_turning_points(&mut self, show_turning_points: bool) {
+        self.show_turning_points = show_turning_points;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn energy_y_min(&self) -> Option<f64> {
+        self.energy_y_min
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_energy_y_min(&mut self, energy_y_min: Option<f64>) {
+        self.energy_y_min = energy_y_min;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn energy_y_max(&self) -> Option<f64> {
+        self.energy_y_max
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_energy_y_max(&mut self, energy_y_max: Option<f64>) {
+        self.energy_y_max = energy_y_max;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn displacement_y_min(&self) -> Option<f64> {
+        self.displacement_y_min
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_displacement_y_min(&mut self, displacement_y_min: Option<f64>) {
+        self.displacement_y_min = displacement_y_min;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn displacement_y_max(&self) -> Option<f64> {
+        self.displacement_y_max
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_displacement_y_max(&mut self, displacement_y_max: Option<f64>) {
+        self.displacement_y_max = displacement_y_max;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn potential_line_style(&self) -> String {
+        self.potential_line_style.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_potential_line_style(&mut self, potential_line_style: String) {
+        self.potential_line_style = potential_line_style;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn kinetic_line_style(&self) -> String {
+        self.kinetic_line_style.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_kinetic_line_style(&mut self, kinetic_line_style: String) {
+        self.kinetic_line_style = kinetic_line_style;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn total_line_style(&self) -> String {
+        self.total_line_style.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_total_line_style(&mut self, total_line_style: String) {
+        self.total_line_style = total_line_style;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn show_energy_drift(&self) -> bool {
+        self.show_energy_drift
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_show_energy_drift(&mut self, show_energy_drift: bool) {
+        self.show_energy_drift = show_energy_drift;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn stacked_energies(&self) -> bool {
+        self.stacked_energies
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_stacked_energies(&mut self, stacked_energies: bool) {
+        self.stacked_energies = stacked_energies;
+    }
+    
+    // Color of an element on the overlay plots (the caller's override, or the built-in color)
+    pub fn element_color(&self, element: &str) -> Option<String> {
+        self.element_colors.iter()
+            .find(|(symbol, _)| symbol == element)
+            .map(|(_, color)| color.clone())
+            .or_else(|| default_element_color(element).map(|color| color.to_string()))
+    }
+    
+    // Override the color (CSS hex string "#rrggbb" or "#rrggbbaa") of an element on the overlay plots
+    pub fn set_element_color(&mut self, element: String, color: String) {
+        self.element_colors.retain(|(symbol, _)| *symbol != element);
+        self.element_colors.push((element, color));
+    }
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        PlotOptions::new()
+    }
+}
+
+// Define struct for the axis colors (CSS hex strings "#rrggbb" or "#rrggbbaa")
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct AxisStyle {
+    axis_color: String,       // Bold axis lines
+    bold_grid_color: String,  // Major gridlines
+    light_grid_color: String, // Minor gridlines
+    text_color: String,       // Axis labels, descriptions and legend text
+    caption_color: String,    // Chart caption
+}
+
+#[wasm_bindgen]
+impl AxisStyle {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> AxisStyle {
+        // Defaults match the Plotters defaults (black axes, translucent black grid)
+        AxisStyle {
+            axis_color: String::from("#000000"),
+            bold_grid_color: String::from("#00000033"),
+            light_grid_color: String::from("#0000001a"),
+            text_color: String::from("#000000"),
+            caption_color: String::from("#000000"),
+        }
+    }
+    
+    // Getters and setters for accessing the fields
+    #[wasm_bindgen(getter)]
+    pub fn axis_color(&self) -> String {
+        self.axis_color.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_axis_color(&mut self, axis_color: String) {
+        self.axis_color = axis_color;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn bold_grid_color(&self) -> String {
+        self.bold_grid_color.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_bold_grid_color(&mut self, bold_grid_color: String) {
+        self.bold_grid_color = bold_grid_color;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn light_grid_color(&self) -> String {
+        self.light_grid_color.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_light_grid_color(&mut self, light_grid_color: String) {
+        self.light_grid_color = light_grid_color;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn text_color(&self) -> String {
+        self.text_color.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_text_color(&mut self, text_color: String) {
+        self.text_color = text_color;
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn caption_color(&self) -> String {
+        self.caption_color.clone()
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_caption_color(&mut self, caption_color: String) {
+        self.caption_color = caption_color;
+    }
+}
+
+impl Default for AxisStyle {
+    fn default() -> Self {
+        AxisStyle::new()
+    }
+}
+
+// Parsed colors of an AxisStyle
+struct AxisColors {
+    axis: RGBAColor,
+    bold_grid: RGBAColor,
+    light_grid: RGBAColor,
+    text: RGBAColor,
+    caption: RGBAColor,
+}
+
+impl AxisStyle {
+    fn colors(&self) -> Result<AxisColors, SimError> {
+        Ok(AxisColors {
+            axis: parse_color(&self.axis_color)?,
+            bold_grid: parse_color(&self.bold_grid_color)?,
+            light_grid: parse_color(&self.light_grid_color)?,
+            text: parse_color(&self.text_color)?,
+            caption: parse_color(&self.caption_color)?,
+        })
+    }
+}
+
+// Helper function to parse a CSS hex color ("#rrggbb" or "#rrggbbaa")
+fn parse_color(color: &str) -> Result<RGBAColor, SimError> {
+    let invalid = || SimError::InvalidInput(format!("Invalid color: {} (expected #rrggbb or #rrggbbaa)", color));
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = if hex.len() == 8 { channel(6)? as f64 / 255.0 } else { 1.0 };
+    Ok(RGBAColor(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+// Helper function to get the scale factor (from atomic units) for an energy unit
+fn energy_unit_factor(unit: &str) -> Result<f64, SimError> {
+    match unit {
+        "au" => Ok(1.0),
+        "eV" => Ok(HARTREE_TO_EV),
+        "kJ/mol" => Ok(HARTREE_TO_KJ_PER_MOL),
+        _ => Err(SimError::UnknownOption { option: "energy unit", value: unit.to_string() }),
+    }
+}
+
+// Helper function to label a quantity with its energy unit (plain label for atomic units)
+fn energy_label(label: &str, unit: &str) -> String {
+    if unit == "au" {
+        label.to_string()
+    } else {
+        format!("{} ({})", label, unit)
+    }
+}
+
+// Helper function to get the caption of a plot: the fixed `title`, or (with auto_caption)
+// the simulation parameters, e.g. "H, Morse, T=300 K, dt=0.5"
+fn plot_caption(title: &str, params: &SimulationParameters, options: &PlotOptions) -> String {
+    if !options.auto_caption() {
+        return title.to_string();
+    }
+    let model = match params.model().as_str() {
+        "harmonic" => String::from("Harmonic"),
+        "morse" => String::from("Morse"),
+        "lennard-jones" => String::from("Lennard-Jones"),
+        "kratzer" => String::from("Kratzer"),
+        other => other.to_string(),
+    };
+    format!("{}, {}, T={} K, dt={}", params.element(), model, params.temperature(), params.timestep())
+}
+
+// Helper function to format an axis label
+// (scientific notation with `significant_figures`, or automatic: plain numbers for moderate
+// magnitudes and 3 significant figures in scientific notation for very small or large ones)
+fn format_axis_label(value: f64, significant_figures: u32) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{}", value);
+    }
+    if significant_figures > 0 {
+        return format!("{:.*e}", significant_figures as usize - 1, value);
+    }
+    let magnitude = value.abs().log10().floor();
+    if (-3.0..4.0).contains(&magnitude) {
+        // (trailing zeros after the decimal point are dropped)
+        let label = format!("{:.*}", (2.0 - magnitude).max(0.0) as usize, value);
+        if label.contains('.') {
+            label.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            label
+        }
+    } else {
+        format!("{:.2e}", value)
+    }
+}
+
+// Pixels of displayed canvas width per x-axis label (when the label count is automatic)
+const PIXELS_PER_X_LABEL: u32 = 60;
+
+// Helper function to get the number of x-axis labels: `requested`, or (if 0) one per PIXELS_PER_X_LABEL
+// of displayed width (the backing width divided by the supersampling factor), at least 2
+fn x_label_count(backing_width: u32, supersampling: u32, requested: u32) -> usize {
+    if requested > 0 {
+        return requested as usize;
+    }
+    let displayed_width = backing_width / supersampling.max(1);
+    (displayed_width / PIXELS_PER_X_LABEL).max(2) as usize
+}
+
+// Helper function to pad an axis range by 10% on both sides
+// (a zero-width range, e.g. a flat trajectory, is widened so the chart can still be built)
+fn padded_range(min: f64, max: f64) -> (f64, f64) {
+    let range = max - min;
+    let padding = if range > 0.0 {
+        range * 0.1
+    } else if max != 0.0 {
+        max.abs() * 0.1
+    } else {
+        1.0
+    };
+    (min - padding, max + padding)
+}
+
+// Helper function to reject an axis range Plotters cannot lay out
+// (non-finite ends, e.g. from a diverged run, would otherwise stall the mesh drawing)
+fn check_axis_range(axis: &str, min: f64, max: f64) -> Result<(), SimError> {
+    if min.is_finite() && max.is_finite() {
+        return Ok(());
+    }
+    Err(SimError::Plot(format!("Cannot plot the {} axis over a non-finite range [{}, {}]", axis, min, max)))
+}
+
+// Helper function to apply fixed ends to an autoscaled axis range (either end may be left to autoscale),
+// so several runs can be drawn on the same axis
+fn fixed_axis_range(axis: &str, (auto_min, auto_max): (f64, f64), min: Option<f64>, max: Option<f64>) -> Result<(f64, f64), SimError> {
+    let (min, max) = (min.unwrap_or(auto_min), max.unwrap_or(auto_max));
+    check_axis_range(axis, min, max)?;
+    if min >= max {
+        return Err(SimError::Plot(format!("Cannot plot the {} axis over an empty range [{}, {}]", axis, min, max)));
+    }
+    Ok((min, max))
+}
+
+// Narrowest axis range laid out in its own values (Plotters places a single tick on ranges narrower
+// than f64::EPSILON, e.g. energies around 1e-19)
+const MIN_UNSCALED_AXIS_RANGE: f64 = 1e-9;
+
+// Helper function to get the power of ten to divide the values of a narrow axis by before laying it out
+// (the labels multiply it back), 1 for axes at least MIN_UNSCALED_AXIS_RANGE wide
+fn axis_value_scale(min: f64, max: f64) -> f64 {
+    let width = max - min;
+    if width > 0.0 && width < MIN_UNSCALED_AXIS_RANGE {
+        10f64.powf(width.log10().floor())
+    } else {
+        1.0
+    }
+}
+
+// Helper function to reject results in SI units (e.g. from the "display" preset), since the plots
+// scale and label atomic units
+fn check_atomic_units(result: &SimulationResult) -> Result<(), SimError> {
+    if result.si_units {
+        return Err(SimError::Plot(String::from("Cannot plot a result in SI units (use the \"raw\" preset or no preset)")));
+    }
+    Ok(())
+}
+
+// Helper function to get the x values and axis label of a time series plot
+// ("steps" gives the step index t / dt, so runs with different timesteps can be compared step by step)
+fn x_axis_values(result: &SimulationResult, params: &SimulationParameters, x_axis: &str) -> Result<(Vec<f64>, &'static str), SimError> {
+    match x_axis {
+        "time" => Ok((result.times.clone(), "Time")),
+        "steps" => Ok((result.times.iter().map(|t| (t / params.timestep()).round()).collect(), "Step")),
+        _ => Err(SimError::UnknownOption { option: "x-axis", value: x_axis.to_string() }),
+    }
+}
+
+// Helper function to get the end of the x-axis of a time series plot: the largest x value, or one
+// timestep (one step) for a single-sample run, so the axis is not zero-width
+fn x_axis_end(xs: &[f64], params: &SimulationParameters, x_axis: &str) -> f64 {
+    let max_x = xs.iter().fold(0.0, |a, &b| f64::max(a, b));
+    if max_x > 0.0 {
+        max_x
+    } else if x_axis == "steps" {
+        1.0
+    } else {
+        params.timestep()
+    }
+}
+
+// Fill color of the potential energy area of the stacked energy plot (opaque light red, so it covers
+// the kinetic energy area below the potential energy)
+const STACKED_POTENTIAL_COLOR: RGBColor = RGBColor(255, 178, 178);
+
+// Helper function to get the y range of the energy plot: the padded range of the energies (which
+// include zero), starting exactly at zero with `clamp_nonnegative` if no energy is negative
+fn energy_axis_range(min_energy: f64, max_energy: f64, clamp_nonnegative: bool) -> (f64, f64) {
+    let (y_min, y_max) = padded_range(min_energy, max_energy);
+    if clamp_nonnegative && min_energy >= 0.0 {
+        (0.0, y_max)
+    } else {
+        (y_min, y_max)
+    }
+}
+
+// Helper function to get the factor converting a length in Bohr to the displacement unit
+// ("percent" needs the equilibrium distance of the element)
+fn displacement_unit_factor(unit: &str, equilibrium_distance: Option<f64>) -> Result<f64, SimError> {
+    match (unit, equilibrium_distance) {
+        ("au", _) => Ok(1.0),
+        ("percent", Some(r_eq)) if r_eq > 0.0 => Ok(100.0 / r_eq),
+        ("percent", _) => Err(SimError::InvalidInput(String::from("Displacement unit percent needs an equilibrium distance"))),
+        _ => Err(SimError::UnknownOption { option: "displacement unit", value: unit.to_string() }),
+    }
+}
+
+// Generation of the newest render (a render started under an older generation is stale)
+static RENDER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// Token identifying one render (see RENDER_GENERATION)
+pub struct RenderToken {
+    generation: u64,
+}
+
+impl RenderToken {
+    // Starts a new render generation, marking every earlier render stale
+    pub fn begin() -> RenderToken {
+        RenderToken { generation: RENDER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1 }
+    }
+
+    // Token of the current generation (for standalone renders that should not mark others stale)
+    #[cfg(feature = "svg")]
+    pub fn current() -> RenderToken {
+        RenderToken { generation: RENDER_GENERATION.load(Ordering::SeqCst) }
+    }
+
+    // Whether a newer render has started (or pending renders were cancelled) since this one began
+    pub fn is_stale(&self) -> bool {
+        RENDER_GENERATION.load(Ordering::SeqCst) != self.generation
+    }
+}
+
+// Function to mark all in-flight renders stale (they skip presenting their drawing)
+pub fn cancel_pending() {
+    RENDER_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+// Function to get a drawing backend for a canvas (applying the supersampling factor)
+pub fn canvas_backend(canvas_id: &str, options: &PlotOptions) -> Result<CanvasBackend, JsValue> {
+    // Get the canvas element (in a browser page; in workers and Node.js there is no document)
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| SimError::Plot(String::from(
+            "No document to find the canvas in (use the offscreen or SVG plots outside a browser page)"
+        )))?;
     let canvas = document.get_element_by_id(canvas_id)
-        .ok_or_else(|| JsValue::from_str(&format!("Cannot find canvas with id {}", canvas_id)))?;
-    let canvas: HtmlCanvasElement = canvas.dyn_into::<HtmlCanvasElement>()?;
+        .ok_or_else(|| SimError::CanvasNotFound(canvas_id.to_string()))?;
+    let canvas: HtmlCanvasElement = canvas.dyn_into::<HtmlCanvasElement>()
+        .map_err(|element| SimError::NotACanvas { id: canvas_id.to_string(), tag: element.tag_name().to_lowercase() })?;
+    
+    // Resize the backing canvas for supersampling
+    apply_supersampling(&canvas, options.supersampling())?;
     
     // Create a drawing backend using the canvas
-    let backend = CanvasBackend::with_canvas_object(canvas)
-        .ok_or_else(|| JsValue::from_str("Cannot create canvas backend"))?;
+    CanvasBackend::with_canvas_object(canvas)
+        .ok_or_else(|| SimError::Plot(String::from("Cannot create canvas backend")).into())
+}
+
+// Helper function to render at `factor` times the resolution while the browser scales the canvas back down
+fn apply_supersampling(canvas: &HtmlCanvasElement, factor: u32) -> Result<(), JsValue> {
+    if factor == 0 {
+        return Err(SimError::InvalidInput(String::from("Supersampling factor must be at least 1")).into());
+    }
+    
+    // Nothing to do if the canvas has never been supersampled
+    let stored_width = canvas.get_attribute("data-logical-width").and_then(|w| w.parse::<u32>().ok());
+    let stored_height = canvas.get_attribute("data-logical-height").and_then(|h| h.parse::<u32>().ok());
+    if factor == 1 && stored_width.is_none() {
+        return Ok(());
+    }
     
-    // Create a drawing area on the backend
-    let root = backend.into_drawing_area();
+    // Remember the displayed size on first use, so repeated renders don't keep growing the canvas
+    let logical_width = stored_width.unwrap_or_else(|| canvas.width());
+    let logical_height = stored_height.unwrap_or_else(|| canvas.height());
+    canvas.set_attribute("data-logical-width", &logical_width.to_string())?;
+    canvas.set_attribute("data-logical-height", &logical_height.to_string())?;
+    
+    // Enlarge the backing store and pin the CSS size to the displayed size
+    canvas.set_width(logical_width * factor);
+    canvas.set_height(logical_height * factor);
+    let style = canvas.style();
+    style.set_property("width", &format!("{}px", logical_width))?;
+    style.set_property("height", &format!("{}px", logical_height))?;
+    
+    Ok(())
+}
+
+// Function to draw the energy and displacement plots of a run on any drawing backends, each given as
+// the result of creating it (e.g. from canvas_backend), and the displacement plot inputs as the result
+// of computing them. Returns the messages of the plots that failed: a failed backend, input or drawing
+// skips only that plot (a stale `token` skips presenting both)
+#[allow(clippy::too_many_arguments)]
+pub fn draw_plots<DE: DrawingBackend, DD: DrawingBackend>(
+    energy_backend: Result<DE, String>,
+    displacement_backend: Result<DD, String>,
+    displacement_inputs: Result<(Option<f64>, Option<f64>), SimError>,
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    options: &PlotOptions,
+    token: &RenderToken
+) -> Vec<String> {
+    let energy = energy_backend.and_then(|backend| {
+        draw_energy_plot(backend.into_drawing_area(), result, params, options, token).map_err(|e| e.to_string())
+    });
+    let displacement = displacement_backend.and_then(|backend| {
+        let (equilibrium_distance, thermal_amplitude) = displacement_inputs.map_err(|e| e.to_string())?;
+        draw_displacement_plot(backend.into_drawing_area(), result, params, options, equilibrium_distance, thermal_amplitude, token)
+            .map_err(|e| e.to_string())
+    });
+    [energy, displacement].into_iter().filter_map(Result::err).collect()
+}
+
+// Helper function to draw the energy plot on any drawing backend (a stale `token` skips presenting)
+fn draw_energy_plot<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    options: &PlotOptions,
+    token: &RenderToken
+) -> Result<(), SimError> {
+    check_atomic_units(result)?;
+    
+    // Get the scale factor for the selected energy unit
+    let unit = options.energy_unit();
+    let factor = energy_unit_factor(&unit)?;
+    let (xs, x_label) = x_axis_values(result, params, &options.x_axis())?;
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
+    
+    // Clear any previous drawing
+    root.fill(&WHITE)
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
+    
+    // Select the energy series to draw as lines
+    // (when stacked, the shown potential and kinetic energies are drawn as areas up to their sum instead)
+    let stacked = options.stacked_energies();
+    let stack_potential = stacked && options.show_potential();
+    let stack_kinetic = stacked && options.show_kinetic();
+    let potential_energies: &[f64] = if options.show_potential() && !stacked { &result.potential_energies } else { &[] };
+    let kinetic_energies: &[f64] = if options.show_kinetic() && !stacked { &result.kinetic_energies } else { &[] };
+    let total_energies: &[f64] = if options.show_total() { &result.total_energies } else { &[] };
+    let stack_top: Vec<f64> = match (stack_potential, stack_kinetic) {
+        (true, true) => result.potential_energies.iter().zip(&result.kinetic_energies).map(|(pe, ke)| pe + ke).collect(),
+        (true, false) => result.potential_energies.clone(),
+        (false, true) => result.kinetic_energies.clone(),
+        (false, false) => Vec::new(),
+    };
+    
+    // Find min and max values (of the visible series) for setting up chart scales
+    let max_x = x_axis_end(&xs, params, &options.x_axis());
+    let min_energy = total_energies.iter()
+        .chain(potential_energies.iter())
+        .chain(kinetic_energies.iter())
+        .chain(stack_top.iter())
+        .fold(0.0, |a, &b| f64::min(a, b)) * factor;
+    let max_energy = total_energies.iter()
+        .chain(potential_energies.iter())
+        .chain(kinetic_energies.iter())
+        .chain(stack_top.iter())
+        .fold(0.0, |a, &b| f64::max(a, b)) * factor;
+    
+    // Add a bit of padding to the min/max values (optionally not below zero), unless the range is fixed
+    let (y_min, y_max) = energy_axis_range(min_energy, max_energy, options.clamp_nonnegative());
+    check_axis_range("x", 0.0, max_x)?;
+    check_axis_range("energy", y_min, y_max)?;
+    let (y_min, y_max) = fixed_axis_range("energy", (y_min, y_max), options.energy_y_min(), options.energy_y_max())?;
+    
+    // Lay out a very narrow energy axis in scaled values (the labels show the energies)
+    let y_scale = axis_value_scale(y_min, y_max);
+    let (y_min, y_max, factor) = (y_min / y_scale, y_max / y_scale, factor / y_scale);
+    
+    // Caption the plot (optionally with the energy drift of the run, in percent)
+    let mut caption = plot_caption("Energy Over Time", params, options);
+    if options.show_energy_drift() {
+        caption = format!("{} (energy drift: {}%)", caption, format_axis_label(100.0 * result.energy_drift(), 0));
+    }
+    
+    // Create a chart context
+    let mut chart = ChartBuilder::on(&root)
+        .caption(caption, ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .build_cartesian_2d(0.0..max_x, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?;
+    
+    // Configure mesh and axes
+    chart.configure_mesh()
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .x_desc(x_label)
+        .y_desc(energy_label("Energy", &unit))
+        .x_labels(x_label_count(root.dim_in_pixel().0, scale, options.x_labels()))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y * y_scale, sig_figs))
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
+    
+    // Optionally draw the kinetic energy stacked on the potential energy: the area up to PE + KE,
+    // covered up to PE by the (opaque) potential energy area, so the top of the stack is the total energy
+    // (a hidden component is left out of the stack)
+    let legend_box = move |(x, y): (i32, i32), color: RGBAColor| {
+        Rectangle::new([(x, y - 5 * scale as i32), (x + 20 * scale as i32, y + 5 * scale as i32)], color.filled())
+    };
+    if stack_kinetic {
+        chart.draw_series(AreaSeries::new(
+            xs.iter().zip(&stack_top).map(|(&x, &y)| (x, y * factor)),
+            0.0,
+            BLUE.mix(0.3)
+        ))
+        .map_err(|e| SimError::Plot(format!("Cannot draw stacked kinetic energy: {}", e)))?
+        .label(energy_label("Kinetic Energy", &unit))
+        .legend(move |point| legend_box(point, BLUE.mix(0.3)));
+    }
+    if stack_potential {
+        chart.draw_series(AreaSeries::new(
+            xs.iter().zip(&result.potential_energies).map(|(&x, &y)| (x, y * factor)),
+            0.0,
+            STACKED_POTENTIAL_COLOR
+        ))
+        .map_err(|e| SimError::Plot(format!("Cannot draw stacked potential energy: {}", e)))?
+        .label(energy_label("Potential Energy", &unit))
+        .legend(move |point| legend_box(point, STACKED_POTENTIAL_COLOR.to_rgba()));
+    }
+    
+    // Draw the potential energy data (if shown)
+    if !potential_energies.is_empty() {
+        let points = xs.iter().zip(potential_energies).map(|(&x, &y)| (x, y * factor)).collect();
+        let label = energy_label("Potential Energy", &unit);
+        draw_styled_series(&mut chart, points, RED, &options.potential_line_style(), scale, label)
+            .map_err(|e| SimError::Plot(format!("Cannot draw potential energy series: {}", e)))?;
+    }
+    
+    // Draw the kinetic energy data (if shown)
+    if !kinetic_energies.is_empty() {
+        let points = xs.iter().zip(kinetic_energies).map(|(&x, &y)| (x, y * factor)).collect();
+        let label = energy_label("Kinetic Energy", &unit);
+        draw_styled_series(&mut chart, points, BLUE, &options.kinetic_line_style(), scale, label)
+            .map_err(|e| SimError::Plot(format!("Cannot draw kinetic energy series: {}", e)))?;
+    }
+    
+    // Draw the total energy data (if shown)
+    if !total_energies.is_empty() {
+        let points = xs.iter().zip(total_energies).map(|(&x, &y)| (x, y * factor)).collect();
+        let label = energy_label("Total Energy", &unit);
+        draw_styled_series(&mut chart, points, GREEN, &options.total_line_style(), scale, label)
+            .map_err(|e| SimError::Plot(format!("Cannot draw total energy series: {}", e)))?;
+    }
+    
+    // Draw the legend (if any series is shown)
+    if !(potential_energies.is_empty() && kinetic_energies.is_empty() && total_energies.is_empty() && stack_top.is_empty()) {
+        chart.configure_series_labels()
+            .label_font(("sans-serif", 12 * scale).into_font().color(&colors.text))
+            .legend_area_size(30 * scale)
+            .margin(10 * scale)
+            .background_style(WHITE.filled())
+            .border_style(colors.axis)
+            .draw()
+            .map_err(|e| SimError::Plot(format!("Cannot draw legend: {}", e)))?;
+    }
+    
+    // Present the drawing (unless a newer render has started)
+    if token.is_stale() {
+        return Ok(());
+    }
+    root.present()
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
+    
+    Ok(())
+}
+
+// Helper function to draw a series in a line style ("solid", "dashed" or "dotted"),
+// with a legend entry in the same style (a single sample, which has no line, is drawn as a point)
+fn draw_styled_series<DB: DrawingBackend>(
+    chart: &mut ChartContext<'_, DB, Cartesian2d<RangedCoordf64, RangedCoordf64>>,
+    points: Vec<(f64, f64)>,
+    color: RGBColor,
+    line_style: &str,
+    scale: u32,
+    label: String
+) -> Result<(), SimError> {
+    let legend_line = move |(x, y): (i32, i32)| vec![(x, y), (x + 20 * scale as i32, y)];
+    let single_point = points.len() == 1;
+    let drawn = match line_style {
+        "solid" | "dashed" | "dotted" if single_point => chart
+            .draw_series(LineSeries::new(points, color.filled()).point_size(2 * scale))
+            .map(|series| {
+                series.label(label)
+                    .legend(move |point| PathElement::new(legend_line(point), color.stroke_width(scale)));
+            }),
+        "solid" => chart.draw_series(LineSeries::new(points, color.filled().stroke_width(scale)))
+            .map(|series| {
+                series.label(label)
+                    .legend(move |point| PathElement::new(legend_line(point), color.stroke_width(scale)));
+            }),
+        "dashed" => chart.draw_series(DashedLineSeries::new(points, 8 * scale, 4 * scale, color.stroke_width(scale)))
+            .map(|series| {
+                series.label(label)
+                    .legend(move |point| DashedPathElement::new(legend_line(point), 5 * scale, 3 * scale, color.stroke_width(scale)));
+            }),
+        "dotted" => chart.draw_series(DottedLineSeries::new(points, 0, 4 * scale, move |c| Circle::new(c, scale, color.filled())))
+            .map(|series| {
+                series.label(label)
+                    .legend(move |point| DottedPathElement::new(legend_line(point), 0, 4 * scale, move |c| Circle::new(c, scale, color.filled())));
+            }),
+        _ => return Err(SimError::UnknownOption { option: "line style", value: line_style.to_string() }),
+    };
+    drawn.map_err(|e| SimError::Plot(e.to_string()))
+}
+
+// Function to render the energy ("energy") or displacement ("displacement") plot as SVG markup of
+// `width` x `height` pixels (see draw_displacement_plot for the extra displacement inputs)
+#[cfg(feature = "svg")]
+#[allow(clippy::too_many_arguments)]
+pub fn render_plot_svg(
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    plot: &str,
+    width: u32,
+    height: u32,
+    options: &PlotOptions,
+    equilibrium_distance: Option<f64>,
+    thermal_amplitude: Option<f64>
+) -> Result<String, SimError> {
+    // (the backend writes the markup into the buffer when its drawing area is dropped)
+    let mut svg = String::new();
+    let backend = SVGBackend::with_string(&mut svg, (width, height));
+    let token = RenderToken::current();
+    match plot {
+        "energy" => draw_energy_plot(backend.into_drawing_area(), result, params, options, &token)?,
+        "displacement" => draw_displacement_plot(
+            backend.into_drawing_area(), result, params, options, equilibrium_distance, thermal_amplitude, &token
+        )?,
+        _ => return Err(SimError::UnknownOption { option: "plot", value: plot.to_string() }),
+    }
+    Ok(svg)
+}
+
+// Helper function to draw the displacement plot on any drawing backend
+// (`equilibrium_distance` is only needed for the "percent" displacement unit,
+// `thermal_amplitude` (Bohr) only for the thermal envelope; a stale `token` skips presenting)
+fn draw_displacement_plot<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    options: &PlotOptions,
+    equilibrium_distance: Option<f64>,
+    thermal_amplitude: Option<f64>,
+    token: &RenderToken
+) -> Result<(), SimError> {
+    check_atomic_units(result)?;
+    
+    // Get the factor converting lengths to the selected unit
+    let unit = options.displacement_unit();
+    let factor = displacement_unit_factor(&unit, equilibrium_distance)?;
+    let displacements: Vec<f64> = result.displacements.iter().map(|d| d * factor).collect();
+    let distances: Vec<f64> = result.distances.iter().map(|d| d * factor).collect();
+    let (xs, x_label) = x_axis_values(result, params, &options.x_axis())?;
+    
+    // Get the thermal envelope (if shown)
+    let envelope = if options.show_thermal_envelope() {
+        let amplitude = thermal_amplitude
+            .ok_or_else(|| SimError::InvalidInput(String::from("Thermal envelope needs the thermal amplitude")))?;
+        Some(amplitude * factor)
+    } else {
+        None
+    };
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
     
     // Clear any previous drawing
     root.fill(&WHITE)
-        .map_err(|e| JsValue::from_str(&format!("Cannot fill background: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
     
     // Find min and max values for setting up chart scales
-    let max_time = result.times.iter().fold(0.0, |a, &b| f64::max(a, b));
-    let min_energy = result.total_energies.iter()
-        .chain(result.potential_energies.iter())
-        .chain(result.kinetic_energies.iter())
+    let max_x = x_axis_end(&xs, params, &options.x_axis());
+    // (including the distances and the envelope when they are shown too)
+    let show_distances = options.show_distances();
+    let shown_distances: &[f64] = if show_distances { &distances } else { &[] };
+    let envelope_bounds: Vec<f64> = envelope.iter().flat_map(|&a| [-a, a]).collect();
+    let min_position = displacements.iter().chain(shown_distances.iter()).chain(envelope_bounds.iter())
         .fold(0.0, |a, &b| f64::min(a, b));
-    let max_energy = result.total_energies.iter()
-        .chain(result.potential_energies.iter())
-        .chain(result.kinetic_energies.iter())
+    let max_position = displacements.iter().chain(shown_distances.iter()).chain(envelope_bounds.iter())
         .fold(0.0, |a, &b| f64::max(a, b));
     
+    // Add a bit of padding to the min/max values, unless the range is fixed
+    let (y_min, y_max) = padded_range(min_position, max_position);
+    check_axis_range("x", 0.0, max_x)?;
+    check_axis_range("displacement", y_min, y_max)?;
+    let (y_min, y_max) = fixed_axis_range(
+        "displacement", (y_min, y_max), options.displacement_y_min(), options.displacement_y_max()
+    )?;
+    
+    // Create a chart context
+    let mut chart = ChartBuilder::on(&root)
+        .caption(plot_caption("Displacement Over Time", params, options), ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .build_cartesian_2d(0.0..max_x, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?;
+    
+    // Configure mesh and axes
+    chart.configure_mesh()
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .x_desc(x_label)
+        .y_desc(match (show_distances, unit.as_str()) {
+            (false, "percent") => "Displacement (% of r_eq)",
+            (true, "percent") => "Displacement / Distance (% of r_eq)",
+            (false, _) => "Displacement",
+            (true, _) => "Displacement / Distance",
+        })
+        .x_labels(x_label_count(root.dim_in_pixel().0, scale, options.x_labels()))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
+    
+    // Optionally fill the area between the curve and the equilibrium line (under the line)
+    if options.fill_displacement() {
+        chart.draw_series(AreaSeries::new(
+            xs.iter().zip(&displacements).map(|(&x, &y)| (x, y)),
+            0.0,
+            BLUE.mix(0.2)
+        ))
+        .map_err(|e| SimError::Plot(format!("Cannot draw position area: {}", e)))?;
+    }
+    
+    // Draw the position data (as a point for a single-sample run)
+    let point_size = if xs.len() == 1 { 2 * scale } else { 0 };
+    chart.draw_series(LineSeries::new(
+        xs.iter().zip(&displacements).map(|(&x, &y)| (x, y)),
+        BLUE.filled().stroke_width(scale)
+    ).point_size(point_size))
+    .map_err(|e| SimError::Plot(format!("Cannot draw position series: {}", e)))?
+    .label("Displacement")
+    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], BLUE.stroke_width(scale)));
+    
+    // Optionally draw the distance data with a legend
+    if show_distances {
+        chart.draw_series(LineSeries::new(
+            xs.iter().zip(&distances).map(|(&x, &y)| (x, y)),
+            RED.filled().stroke_width(scale)
+        ).point_size(point_size))
+        .map_err(|e| SimError::Plot(format!("Cannot draw distance series: {}", e)))?
+        .label("Distance")
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], RED.stroke_width(scale)));
+    }
+    
+    // Optionally draw the thermal envelope as dashed lines at ±amplitude
+    if let Some(amplitude) = envelope {
+        for (sign, label) in [(1.0, "Thermal amplitude"), (-1.0, "")] {
+            let series = chart.draw_series(DashedLineSeries::new(
+                [(0.0, sign * amplitude), (max_x, sign * amplitude)],
+                5 * scale,
+                5 * scale,
+                BLACK.mix(0.6).stroke_width(scale)
+            ))
+            .map_err(|e| SimError::Plot(format!("Cannot draw thermal envelope: {}", e)))?;
+            if !label.is_empty() {
+                series.label(label)
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], BLACK.mix(0.6).stroke_width(scale)));
+            }
+        }
+    }
+    
+    // Optionally mark the turning points (where the velocity changes sign) with small circles
+    let show_turning_points = options.show_turning_points();
+    if show_turning_points {
+        let radius = 3 * scale;
+        chart.draw_series(result.turning_point_indices().into_iter().map(|i| {
+            Circle::new((xs[i], displacements[i]), radius, MAGENTA.filled())
+        }))
+        .map_err(|e| SimError::Plot(format!("Cannot draw turning points: {}", e)))?
+        .label("Turning points")
+        .legend(move |(x, y)| Circle::new((x + 10 * scale as i32, y), radius, MAGENTA.filled()));
+    }
+    
+    // Draw the legend when there is more than the displacement series
+    if show_distances || envelope.is_some() || show_turning_points {
+        chart.configure_series_labels()
+            .label_font(("sans-serif", 12 * scale).into_font().color(&colors.text))
+            .legend_area_size(30 * scale)
+            .margin(10 * scale)
+            .background_style(WHITE.filled())
+            .border_style(colors.axis)
+            .draw()
+            .map_err(|e| SimError::Plot(format!("Cannot draw legend: {}", e)))?;
+    }
+    
+    // Present the drawing (unless a newer render has started)
+    if token.is_stale() {
+        return Ok(());
+    }
+    root.present()
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
+    
+    Ok(())
+}
+
+// Function to render the energy exchange plot (kinetic against potential energy)
+// (a conserved total energy traces the straight line KE = E0 - PE; deviations show energy drift)
+pub fn render_energy_exchange_plot(
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    canvas_id: &str,
+    options: &PlotOptions
+) -> Result<(), JsValue> {
+    let backend = canvas_backend(canvas_id, options)?;
+    Ok(draw_energy_exchange_plot(backend.into_drawing_area(), result, params, options)?)
+}
+
+// Helper function to draw the energy exchange plot on any drawing backend
+fn draw_energy_exchange_plot<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    options: &PlotOptions
+) -> Result<(), SimError> {
+    check_atomic_units(result)?;
+    if result.potential_energies.len() != result.kinetic_energies.len() {
+        return Err(SimError::Plot(format!(
+            "Cannot pair {} potential energies with {} kinetic energies",
+            result.potential_energies.len(), result.kinetic_energies.len()
+        )));
+    }
+    if result.potential_energies.is_empty() {
+        return Err(SimError::InvalidInput(String::from("Energy exchange plot needs at least one sample")));
+    }
+    
+    // Get the scale factor for the selected energy unit
+    let unit = options.energy_unit();
+    let factor = energy_unit_factor(&unit)?;
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
+    
+    // Clear any previous drawing
+    root.fill(&WHITE)
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
+    
+    // Find min and max values for setting up chart scales
+    let min_potential = result.potential_energies.iter().fold(f64::INFINITY, |a, &b| f64::min(a, b)) * factor;
+    let max_potential = result.potential_energies.iter().fold(f64::NEG_INFINITY, |a, &b| f64::max(a, b)) * factor;
+    let max_kinetic = result.kinetic_energies.iter().fold(0.0, |a, &b| f64::max(a, b)) * factor;
+    
     // Add a bit of padding to the min/max values
-    let y_range = max_energy - min_energy;
-    let y_min = min_energy - y_range * 0.1;
-    let y_max = max_energy + y_range * 0.1;
+    let (x_min, x_max) = padded_range(min_potential, max_potential);
+    let (y_min, y_max) = padded_range(0.0, max_kinetic);
+    check_axis_range("potential energy", x_min, x_max)?;
+    check_axis_range("kinetic energy", y_min, y_max)?;
     
     // Create a chart context
     let mut chart = ChartBuilder::on(&root)
-        .caption("Energy Over Time", ("sans-serif", 20).into_font())
-        .margin(10)
-        .x_label_area_size(40)
-        .y_label_area_size(60)
-        .build_cartesian_2d(0.0..max_time, y_min..y_max)
-        .map_err(|e| JsValue::from_str(&format!("Cannot build chart: {}", e)))?;
+        .caption(plot_caption("Energy Exchange", params, options), ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .build_cartesian_2d(x_min..x_max, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?;
     
     // Configure mesh and axes
     chart.configure_mesh()
-        .x_desc("Time")
-        .y_desc("Energy")
-        .x_labels(20)
-        .x_label_formatter(&|x| format!("{}", x.floor() as i32))
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .x_desc(energy_label("Potential Energy", &unit))
+        .y_desc(energy_label("Kinetic Energy", &unit))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
         .draw()
-        .map_err(|e| JsValue::from_str(&format!("Cannot draw mesh: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
     
-    // Draw the potential energy data
+    // Draw the line of exact energy conservation (KE = E0 - PE)
+    let e0 = result.total_energies.first().copied().unwrap_or(0.0) * factor;
     chart.draw_series(LineSeries::new(
-        result.times.iter().zip(&result.potential_energies).map(|(&x, &y)| (x, y)),
-        RED.filled()
+        [(x_min, e0 - x_min), (x_max, e0 - x_max)],
+        GREEN.mix(0.6).stroke_width(scale)
     ))
-    .map_err(|e| JsValue::from_str(&format!("Cannot draw potential energy series: {}", e)))?
-    .label("Potential Energy")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
+    .map_err(|e| SimError::Plot(format!("Cannot draw conservation line: {}", e)))?
+    .label("KE + PE = E0")
+    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], GREEN.stroke_width(scale)));
     
-    // Draw the kinetic energy data
+    // Draw the (PE, KE) pairs of the trajectory
     chart.draw_series(LineSeries::new(
-        result.times.iter().zip(&result.kinetic_energies).map(|(&x, &y)| (x, y)),
-        BLUE.filled()
+        result.potential_energies.iter().zip(&result.kinetic_energies).map(|(&x, &y)| (x * factor, y * factor)),
+        BLUE.filled().stroke_width(scale)
     ))
-    .map_err(|e| JsValue::from_str(&format!("Cannot draw kinetic energy series: {}", e)))?
-    .label("Kinetic Energy")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
+    .map_err(|e| SimError::Plot(format!("Cannot draw energy exchange series: {}", e)))?
+    .label("Trajectory")
+    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], BLUE.stroke_width(scale)));
     
-    // Draw the total energy data
+    // Draw the legend
+    chart.configure_series_labels()
+        .label_font(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .legend_area_size(30 * scale)
+        .margin(10 * scale)
+        .background_style(WHITE.filled())
+        .border_style(colors.axis)
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw legend: {}", e)))?;
+    
+    // Present the drawing
+    root.present()
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
+    
+    Ok(())
+}
+
+// Function to render the displacement (left axis) and total energy (right axis) over time on one chart
+pub fn render_displacement_energy_dual(
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    canvas_id: &str,
+    options: &PlotOptions
+) -> Result<(), JsValue> {
+    let backend = canvas_backend(canvas_id, options)?;
+    Ok(draw_displacement_energy_dual(backend.into_drawing_area(), result, params, options)?)
+}
+
+// Helper function to draw the dual-axis displacement and energy plot on any drawing backend
+// (with the x-axis and the fixed displacement and energy ranges of `options`)
+fn draw_displacement_energy_dual<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    result: &SimulationResult,
+    params: &SimulationParameters,
+    options: &PlotOptions
+) -> Result<(), SimError> {
+    check_atomic_units(result)?;
+    
+    // Get the scale factor for the selected energy unit
+    let unit = options.energy_unit();
+    let factor = energy_unit_factor(&unit)?;
+    let (xs, x_label) = x_axis_values(result, params, &options.x_axis())?;
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
+    
+    // Clear any previous drawing
+    root.fill(&WHITE)
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
+    
+    // Find min and max values for setting up both chart scales
+    let max_x = x_axis_end(&xs, params, &options.x_axis());
+    let min_position = result.displacements.iter().fold(0.0, |a, &b| f64::min(a, b));
+    let max_position = result.displacements.iter().fold(0.0, |a, &b| f64::max(a, b));
+    let min_energy = result.total_energies.iter().fold(f64::INFINITY, |a, &b| f64::min(a, b)) * factor;
+    let max_energy = result.total_energies.iter().fold(f64::NEG_INFINITY, |a, &b| f64::max(a, b)) * factor;
+    
+    // Add a bit of padding to the min/max values, unless the ranges are fixed
+    let (y_min, y_max) = padded_range(min_position, max_position);
+    let (e_min, e_max) = padded_range(min_energy, max_energy);
+    check_axis_range("x", 0.0, max_x)?;
+    check_axis_range("displacement", y_min, y_max)?;
+    check_axis_range("energy", e_min, e_max)?;
+    let (y_min, y_max) = fixed_axis_range(
+        "displacement", (y_min, y_max), options.displacement_y_min(), options.displacement_y_max()
+    )?;
+    let (e_min, e_max) = fixed_axis_range("energy", (e_min, e_max), options.energy_y_min(), options.energy_y_max())?;
+    
+    // Create a chart context (displacement on the left, energy on the right axis)
+    let mut chart = ChartBuilder::on(&root)
+        .caption(plot_caption("Displacement and Energy Over Time", params, options), ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .right_y_label_area_size(60 * scale)
+        .build_cartesian_2d(0.0..max_x, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?
+        .set_secondary_coord(0.0..max_x, e_min..e_max);
+    
+    // Configure mesh and both axes
+    chart.configure_mesh()
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .x_desc(x_label)
+        .y_desc("Displacement")
+        .x_labels(x_label_count(root.dim_in_pixel().0, scale, options.x_labels()))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
+    chart.configure_secondary_axes()
+        .axis_style(colors.axis)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .y_desc(energy_label("Total Energy", &unit))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw secondary axis: {}", e)))?;
+    
+    // Draw the position data (left axis)
     chart.draw_series(LineSeries::new(
-        result.times.iter().zip(&result.total_energies).map(|(&x, &y)| (x, y)),
-        GREEN.filled()
+        xs.iter().zip(&result.displacements).map(|(&x, &y)| (x, y)),
+        BLUE.filled().stroke_width(scale)
+    ))
+    .map_err(|e| SimError::Plot(format!("Cannot draw position series: {}", e)))?
+    .label("Displacement")
+    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], BLUE.stroke_width(scale)));
+    
+    // Draw the total energy data (right axis)
+    chart.draw_secondary_series(LineSeries::new(
+        xs.iter().zip(&result.total_energies).map(|(&x, &y)| (x, y * factor)),
+        GREEN.filled().stroke_width(scale)
     ))
-    .map_err(|e| JsValue::from_str(&format!("Cannot draw total energy series: {}", e)))?
-    .label("Total Energy")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], GREEN));
+    .map_err(|e| SimError::Plot(format!("Cannot draw total energy series: {}", e)))?
+    .label(energy_label("Total Energy", &unit))
+    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], GREEN.stroke_width(scale)));
     
     // Draw the legend
     chart.configure_series_labels()
+        .label_font(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .legend_area_size(30 * scale)
+        .margin(10 * scale)
         .background_style(WHITE.filled())
-        .border_style(BLACK)
+        .border_style(colors.axis)
         .draw()
-        .map_err(|e| JsValue::from_str(&format!("Cannot draw legend: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot draw legend: {}", e)))?;
     
     // Present the drawing
     root.present()
-        .map_err(|e| JsValue::from_str(&format!("Cannot present chart: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
     
     Ok(())
 }
 
-// Function to render the displacement plot
-pub fn render_displacement_plot(result: &SimulationResult, canvas_id: &str) -> Result<(), JsValue> {
-    // Get the canvas element
-    let document = web_sys::window().unwrap().document().unwrap();
-    let canvas = document.get_element_by_id(canvas_id)
-        .ok_or_else(|| JsValue::from_str(&format!("Cannot find canvas with id {}", canvas_id)))?;
-    let canvas: HtmlCanvasElement = canvas.dyn_into::<HtmlCanvasElement>()?;
+// Line colors of the runs on the model comparison plot (cycled if there are more runs)
+const COMPARISON_COLORS: [RGBColor; 4] = [BLUE, RED, GREEN, MAGENTA];
+
+// Built-in colors of the elements on the overlay plots (the same element has the same color on every chart)
+const ELEMENT_COLORS: &[(&str, &str)] = &[("H", "#ff0000"), ("Hg", "#808080"), ("Ar", "#00ff00")];
+
+// Helper function to get the built-in color of an element
+fn default_element_color(element: &str) -> Option<&'static str> {
+    ELEMENT_COLORS.iter().find(|(symbol, _)| *symbol == element).map(|(_, color)| *color)
+}
+
+// Helper function to get the line color of the `index`-th series of an overlay plot: the color of the
+// element if the label is an element symbol (see PlotOptions::element_color), else the next comparison color
+fn series_color(label: &str, index: usize, options: &PlotOptions) -> Result<RGBAColor, SimError> {
+    match options.element_color(label) {
+        Some(color) => parse_color(&color),
+        None => Ok(COMPARISON_COLORS[index % COMPARISON_COLORS.len()].to_rgba()),
+    }
+}
+
+// Function to render the displacements of several labelled runs on one plot (with a legend)
+pub fn render_comparison_plot(
+    runs: &[ComparisonRun],
+    caption: &str,
+    canvas_id: &str,
+    options: &PlotOptions
+) -> Result<(), JsValue> {
+    let backend = canvas_backend(canvas_id, options)?;
+    Ok(draw_comparison_plot(backend.into_drawing_area(), runs, caption, options)?)
+}
+
+// Helper function to draw the comparison plot on any drawing backend
+// (the x values of each run come from its own parameters; the axis ends after the longest run)
+fn draw_comparison_plot<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    runs: &[ComparisonRun],
+    caption: &str,
+    options: &PlotOptions
+) -> Result<(), SimError> {
+    let [(_, first_params, _), ..] = runs else {
+        return Err(SimError::InvalidInput(String::from("Comparison plot needs at least one run")));
+    };
+    for (_, _, result) in runs {
+        check_atomic_units(result)?;
+    }
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
     
-    // Create a drawing backend using the canvas
-    let backend = CanvasBackend::with_canvas_object(canvas)
-        .ok_or_else(|| JsValue::from_str("Cannot create canvas backend"))?;
+    // Get the x values (time or step number) of each run
+    let mut x_values = Vec::with_capacity(runs.len());
+    let mut x_label = "Time";
+    for (_, params, result) in runs {
+        let (xs, label) = x_axis_values(result, params, &options.x_axis())?;
+        x_values.push(xs);
+        x_label = label;
+    }
+    
+    // Clear any previous drawing
+    root.fill(&WHITE)
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
+    
+    // Find min and max values (over all runs) for setting up chart scales
+    let all_xs: Vec<f64> = x_values.iter().flatten().copied().collect();
+    let max_x = x_axis_end(&all_xs, first_params, &options.x_axis());
+    let min_position = runs.iter().flat_map(|(_, _, r)| r.displacements.iter()).fold(0.0, |a, &b| f64::min(a, b));
+    let max_position = runs.iter().flat_map(|(_, _, r)| r.displacements.iter()).fold(0.0, |a, &b| f64::max(a, b));
+    
+    // Add a bit of padding to the min/max values
+    let (y_min, y_max) = padded_range(min_position, max_position);
+    check_axis_range("x", 0.0, max_x)?;
+    check_axis_range("displacement", y_min, y_max)?;
+    
+    // Create a chart context
+    let mut chart = ChartBuilder::on(&root)
+        .caption(caption, ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .build_cartesian_2d(0.0..max_x, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?;
+    
+    // Configure mesh and axes
+    chart.configure_mesh()
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .x_desc(x_label)
+        .y_desc("Displacement")
+        .x_labels(x_label_count(root.dim_in_pixel().0, scale, options.x_labels()))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
+    
+    // Draw the displacement data of each run
+    for (i, ((label, _, result), xs)) in runs.iter().zip(&x_values).enumerate() {
+        let color = series_color(label, i, options)?;
+        chart.draw_series(LineSeries::new(
+            xs.iter().zip(&result.displacements).map(|(&x, &y)| (x, y)),
+            color.filled().stroke_width(scale)
+        ))
+        .map_err(|e| SimError::Plot(format!("Cannot draw {} series: {}", label, e)))?
+        .label(*label)
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], color.stroke_width(scale)));
+    }
+    
+    // Draw the legend
+    chart.configure_series_labels()
+        .label_font(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .legend_area_size(30 * scale)
+        .margin(10 * scale)
+        .background_style(WHITE.filled())
+        .border_style(colors.axis)
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw legend: {}", e)))?;
     
-    // Create a drawing area on the backend
-    let root = backend.into_drawing_area();
+    // Present the drawing
+    root.present()
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
+    
+    Ok(())
+}
+
+// Function to render the harmonic potentials of several elements on one plot (with a legend)
+// (`curves` e.g. from sim::harmonic_potential_curves)
+pub fn render_potentials_comparison(
+    curves: &[PotentialCurve],
+    canvas_id: &str,
+    options: &PlotOptions
+) -> Result<(), JsValue> {
+    let backend = canvas_backend(canvas_id, options)?;
+    Ok(draw_potentials_comparison(backend.into_drawing_area(), curves, options)?)
+}
+
+// Helper function to draw the potentials comparison on any drawing backend
+fn draw_potentials_comparison<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    curves: &[PotentialCurve],
+    options: &PlotOptions
+) -> Result<(), SimError> {
+    // Get the scale factor for the selected energy unit
+    let unit = options.energy_unit();
+    let factor = energy_unit_factor(&unit)?;
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
     
     // Clear any previous drawing
     root.fill(&WHITE)
-        .map_err(|e| JsValue::from_str(&format!("Cannot fill background: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
+    
+    // Find min and max values (over all curves) for setting up chart scales
+    let points = || curves.iter().flat_map(|(_, curve)| curve.iter());
+    let min_r = points().fold(0.0, |a, &(r, _)| f64::min(a, r));
+    let max_r = points().fold(0.0, |a, &(r, _)| f64::max(a, r));
+    let max_energy = points().fold(0.0, |a, &(_, v)| f64::max(a, v)) * factor;
+    
+    // Add a bit of padding to the energy range
+    let (y_min, y_max) = energy_axis_range(0.0, max_energy, true);
+    
+    // Create a chart context
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Harmonic Potentials", ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .build_cartesian_2d(min_r..max_r, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?;
+    
+    // Configure mesh and axes
+    chart.configure_mesh()
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .x_desc("Displacement")
+        .y_desc(energy_label("Potential Energy", &unit))
+        .x_labels(x_label_count(root.dim_in_pixel().0, scale, options.x_labels()))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
+    
+    // Draw the potential of each element
+    for (i, (element, curve)) in curves.iter().enumerate() {
+        let color = series_color(element, i, options)?;
+        chart.draw_series(LineSeries::new(
+            curve.iter().map(|&(r, v)| (r, v * factor)),
+            color.filled().stroke_width(scale)
+        ))
+        .map_err(|e| SimError::Plot(format!("Cannot draw {} series: {}", element, e)))?
+        .label(element.as_str())
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20 * scale as i32, y)], color.stroke_width(scale)));
+    }
+    
+    // Draw the legend
+    chart.configure_series_labels()
+        .label_font(("sans-serif", 12 * scale).into_font().color(&colors.text))
+        .legend_area_size(30 * scale)
+        .margin(10 * scale)
+        .background_style(WHITE.filled())
+        .border_style(colors.axis)
+        .draw()
+        .map_err(|e| SimError::Plot(format!("Cannot draw legend: {}", e)))?;
+    
+    // Present the drawing
+    root.present()
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
+    
+    Ok(())
+}
+
+// Function to render the residuals x_numeric(t) - x_analytic(t) of a harmonic run
+// (`residuals` e.g. from sim::harmonic_residuals)
+pub fn render_residual_plot(
+    residuals: &[(f64, f64)],
+    params: &SimulationParameters,
+    canvas_id: &str,
+    options: &PlotOptions
+) -> Result<(), JsValue> {
+    let backend = canvas_backend(canvas_id, options)?;
+    Ok(draw_residual_plot(backend.into_drawing_area(), residuals, params, options)?)
+}
+
+// Helper function to draw the residual plot on any drawing backend
+fn draw_residual_plot<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    residuals: &[(f64, f64)],
+    params: &SimulationParameters,
+    options: &PlotOptions
+) -> Result<(), SimError> {
+    let scale = options.supersampling();
+    let colors = options.axis_style().colors()?;
+    let sig_figs = options.significant_figures();
+    
+    // Clear any previous drawing
+    root.fill(&WHITE)
+        .map_err(|e| SimError::Plot(format!("Cannot fill background: {}", e)))?;
     
     // Find min and max values for setting up chart scales
-    let max_time = result.times.iter().fold(0.0, |a, &b| f64::max(a, b));
-    let min_position = result.displacements.iter().fold(0.0, |a, &b| f64::min(a, b));
-    let max_position = result.displacements.iter().fold(0.0, |a, &b| f64::max(a, b));
+    let times: Vec<f64> = residuals.iter().map(|&(t, _)| t).collect();
+    let max_x = x_axis_end(&times, params, "time");
+    let min_residual = residuals.iter().fold(0.0, |a, &(_, r)| f64::min(a, r));
+    let max_residual = residuals.iter().fold(0.0, |a, &(_, r)| f64::max(a, r));
     
     // Add a bit of padding to the min/max values
-    let y_range = max_position - min_position;
-    let y_min = min_position - y_range * 0.1;
-    let y_max = max_position + y_range * 0.1;
+    let (y_min, y_max) = padded_range(min_residual, max_residual);
+    check_axis_range("x", 0.0, max_x)?;
+    check_axis_range("residual", y_min, y_max)?;
     
     // Create a chart context
     let mut chart = ChartBuilder::on(&root)
-        .caption("Displacement Over Time", ("sans-serif", 20).into_font())
-        .margin(10)
-        .x_label_area_size(40)
-        .y_label_area_size(60)
-        .build_cartesian_2d(0.0..max_time, y_min..y_max)
-        .map_err(|e| JsValue::from_str(&format!("Cannot build chart: {}", e)))?;
+        .caption(plot_caption("Residual Against the Analytic Solution", params, options), ("sans-serif", 20 * scale).into_font().color(&colors.caption))
+        .margin(10 * scale)
+        .x_label_area_size(40 * scale)
+        .y_label_area_size(60 * scale)
+        .build_cartesian_2d(0.0..max_x, y_min..y_max)
+        .map_err(|e| SimError::Plot(format!("Cannot build chart: {}", e)))?;
     
     // Configure mesh and axes
     chart.configure_mesh()
+        .axis_style(colors.axis)
+        .bold_line_style(colors.bold_grid)
+        .light_line_style(colors.light_grid)
+        .label_style(("sans-serif", 12 * scale).into_font().color(&colors.text))
         .x_desc("Time")
-        .y_desc("Displacement")
-        .x_labels(20)
-        .x_label_formatter(&|x| format!("{}", x.floor() as i32))
+        .y_desc("Residual")
+        .x_labels(x_label_count(root.dim_in_pixel().0, scale, options.x_labels()))
+        .x_label_formatter(&|x| format_axis_label(*x, sig_figs))
+        .y_label_formatter(&|y| format_axis_label(*y, sig_figs))
         .draw()
-        .map_err(|e| JsValue::from_str(&format!("Cannot draw mesh: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot draw mesh: {}", e)))?;
     
-    // Draw the position data
-    chart.draw_series(LineSeries::new(
-        result.times.iter().zip(&result.displacements).map(|(&x, &y)| (x, y)),
-        BLUE.filled()
-    ))
-    .map_err(|e| JsValue::from_str(&format!("Cannot draw position series: {}", e)))?;
+    // Draw the residuals
+    chart.draw_series(LineSeries::new(residuals.iter().copied(), BLUE.filled().stroke_width(scale)))
+        .map_err(|e| SimError::Plot(format!("Cannot draw residual series: {}", e)))?;
     
     // Present the drawing
     root.present()
-        .map_err(|e| JsValue::from_str(&format!("Cannot present chart: {}", e)))?;
+        .map_err(|e| SimError::Plot(format!("Cannot present chart: {}", e)))?;
     
     Ok(())
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    // X-axis labels: fewer on narrow canvases, unless a count is requested
+    #[test]
+    fn narrow_canvases_get_fewer_x_labels() {
+        assert_eq!(x_label_count(200, 1, 0), 3);
+        assert_eq!(x_label_count(400, 2, 0), 3);
+        assert_eq!(x_label_count(1200, 1, 0), 20);
+        assert_eq!(x_label_count(60, 1, 0), 2);
+        assert_eq!(x_label_count(200, 1, 20), 20);
+    }
+
+    // Energy axis: starts at zero when clamped and no energy is negative
+    #[test]
+    fn clamped_energy_axis_starts_at_zero() {
+        assert_eq!(energy_axis_range(0.0, 2.0, true), (0.0, 2.2));
+        assert!(energy_axis_range(0.0, 2.0, false).0 < 0.0);
+        assert!(energy_axis_range(-1.0, 2.0, true).0 < -1.0);
+    }
+
+    // Render tokens: a newer render or cancel_pending makes earlier tokens stale
+    #[test]
+    fn newer_renders_make_older_tokens_stale() {
+        let first = RenderToken::begin();
+        assert!(!first.is_stale());
+        let second = RenderToken::begin();
+        assert!(first.is_stale());
+        assert!(!second.is_stale());
+        cancel_pending();
+        assert!(second.is_stale());
+        assert!(!RenderToken::begin().is_stale());
+    }
+
+    // SVG export: markup of an SVG document with the caption
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_exports_as_svg() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let svg = render_plot_svg(&result, &params, "energy", 800, 400, &PlotOptions::default(), None, None).unwrap();
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("Energy Over Time"));
+    }
+
+    // Fixed y-range: the displacement axis spans the requested range instead of the data
+    #[cfg(feature = "svg")]
+    #[test]
+    fn displacement_plot_with_fixed_y_range() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_displacement_y_min(Some(-2.0));
+        options.set_displacement_y_max(Some(2.0));
+        let svg = render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).unwrap();
+        assert!(svg.contains(">\n-2\n<") && svg.contains(">\n2\n<"));
+
+        // (an empty range is reported instead of drawn)
+        options.set_displacement_y_max(Some(-2.0));
+        assert!(render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).is_err());
+    }
+
+    // Line styles: a dotted series is drawn as circles, a dashed one as many short segments
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_with_dashed_and_dotted_series() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        let solid = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        options.set_kinetic_line_style(String::from("dashed"));
+        options.set_potential_line_style(String::from("dotted"));
+        let styled = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert!(!solid.contains("<circle") && styled.contains("<circle"));
+        assert!(styled.matches("<polyline").count() > solid.matches("<polyline").count() + 10);
+        options.set_total_line_style(String::from("wavy"));
+        assert!(render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).is_err());
+    }
+
+    // Energy drift caption: the title is followed by the drift in percent
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_caption_shows_the_drift() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_show_energy_drift(true);
+        let svg = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        let drift = format_axis_label(100.0 * result.energy_drift(), 0);
+        assert!(svg.contains(&format!(">\nEnergy Over Time (energy drift: {}%)\n<", drift)), "{}", drift);
+    }
+
+    // Single sample: a zero-duration run is drawn as points on an x-axis of one timestep
+    #[cfg(feature = "svg")]
+    #[test]
+    fn single_sample_run_is_drawn_as_points() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 0.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        assert_eq!(result.times.len(), 1);
+        for plot in ["displacement", "energy"] {
+            let svg = render_plot_svg(&result, &params, plot, 800, 400, &PlotOptions::default(), None, None).unwrap();
+            assert!(svg.contains("<circle"), "{}", plot);
+            assert!(svg.contains(">\n0.5\n<"), "{}", plot);
+        }
+    }
+
+    // Stacked energies: two filled areas (and their legend entries) instead of the potential and kinetic lines
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_with_stacked_areas() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        let lines = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        options.set_stacked_energies(true);
+        let stacked = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert_eq!(lines.matches("<polygon").count(), 0);
+        assert_eq!(stacked.matches("<polygon").count(), 2);
+        assert!(stacked.contains(">\nKinetic Energy\n<") && stacked.contains(">\nPotential Energy\n<"));
+    }
+
+    // Element colors: Ar is drawn in the same color whether it is plotted first or second, unless overridden
+    #[cfg(feature = "svg")]
+    #[test]
+    fn element_keeps_its_color_across_comparisons() {
+        let render = |elements: &[&str], options: &PlotOptions| {
+            let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+            let curves = crate::sim::harmonic_potential_curves(&elements).unwrap();
+            let mut svg = String::new();
+            draw_potentials_comparison(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &curves, options).unwrap();
+            svg
+        };
+        let mut options = PlotOptions::default();
+        let both = render(&["H", "Ar"], &options);
+        let argon = render(&["Ar"], &options);
+        assert!(both.contains("#00FF00") && argon.contains("#00FF00"));
+        assert!(!argon.contains("#0000FF"));
+        options.set_element_color(String::from("Ar"), String::from("#123456"));
+        assert!(render(&["Ar"], &options).contains("#123456"));
+    }
+
+    // Residual plot: the residuals of a harmonic run are drawn with their caption
+    #[cfg(feature = "svg")]
+    #[test]
+    fn harmonic_residual_plot() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 2000.0, 0.5, 300.0);
+        let residuals = crate::sim::harmonic_residuals(&params).unwrap();
+        let mut svg = String::new();
+        draw_residual_plot(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &residuals, &params, &PlotOptions::default())
+            .unwrap();
+        assert!(svg.contains(">\nResidual Against the Analytic Solution\n<"));
+        assert!(svg.contains("<polyline"));
+    }
+
+    // Potentials comparison: one legend entry per element
+    #[cfg(feature = "svg")]
+    #[test]
+    fn potentials_comparison_of_two_elements() {
+        let curves = crate::sim::harmonic_potential_curves(&[String::from("H"), String::from("Ar")]).unwrap();
+        let mut svg = String::new();
+        draw_potentials_comparison(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &curves, &PlotOptions::default())
+            .unwrap();
+        assert!(svg.contains("Harmonic Potentials"));
+        assert!(svg.contains(">\nH\n<") && svg.contains(">\nAr\n<"));
+    }
+
+    // Energy unit: the legend entries name the unit when it is not atomic units
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_legend_in_electronvolts() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        let atomic = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert!(atomic.contains(">\nPotential Energy\n<"));
+        options.set_energy_unit(String::from("eV"));
+        let svg = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        for label in ["Potential Energy (eV)", "Kinetic Energy (eV)", "Total Energy (eV)"] {
+            assert!(svg.contains(&format!(">\n{}\n<", label)), "{}", label);
+        }
+    }
+
+    // Supersampling: at 2x the plot renders on the doubled backing size with doubled fonts and lines
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_supersampled_twice() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        let plain = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        options.set_supersampling(2);
+        let svg = render_plot_svg(&result, &params, "energy", 1600, 800, &options, None, None).unwrap();
+        // (the first text element is the caption)
+        let caption_size = |svg: &str| -> f64 {
+            let start = svg.find("font-size=\"").unwrap() + "font-size=\"".len();
+            svg[start..].split('"').next().unwrap().parse().unwrap()
+        };
+        assert!((caption_size(&svg) - 2.0 * caption_size(&plain)).abs() < 1e-9);
+        assert!(svg.contains(r#"stroke-width="2""#) && !plain.contains(r#"stroke-width="2""#));
+    }
+
+    // Axis style: every element of an all-custom palette is drawn in its color, on both plots
+    #[cfg(feature = "svg")]
+    #[test]
+    fn plots_with_a_custom_axis_palette() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut style = AxisStyle::new();
+        style.set_axis_color(String::from("#111111"));
+        style.set_bold_grid_color(String::from("#222222"));
+        style.set_light_grid_color(String::from("#333333"));
+        style.set_text_color(String::from("#444444"));
+        style.set_caption_color(String::from("#555555"));
+        let mut options = PlotOptions::default();
+        options.set_axis_style(style);
+        for plot in ["energy", "displacement"] {
+            let svg = render_plot_svg(&result, &params, plot, 800, 400, &options, None, None).unwrap();
+            for color in ["#111111", "#222222", "#333333", "#444444", "#555555"] {
+                assert!(svg.contains(color), "{}: {}", plot, color);
+            }
+            assert!(!svg.contains("#000000"), "{}", plot);
+        }
+    }
+
+    // Filled displacement: the area down to the equilibrium line is drawn as a polygon under the curve
+    #[cfg(feature = "svg")]
+    #[test]
+    fn displacement_plot_with_filled_area() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        let line = render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).unwrap();
+        options.set_fill_displacement(true);
+        let filled = render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).unwrap();
+        assert_eq!(line.matches("<polygon").count(), 0);
+        assert_eq!(filled.matches("<polygon").count(), 1);
+        assert!(filled.contains("<polyline"));
+    }
+
+    // Displacement and distance: both series get a legend entry and the axis spans both (up to r_e + x)
+    #[cfg(feature = "svg")]
+    #[test]
+    fn displacement_plot_with_distances() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        let alone = render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).unwrap();
+        options.set_show_distances(true);
+        let both = render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).unwrap();
+        assert!(!alone.contains(">\nDistance\n<") && !alone.contains(">\n1.4\n<"));
+        assert!(both.contains(">\nDisplacement\n<") && both.contains(">\nDistance\n<"));
+        assert!(both.contains(">\nDisplacement / Distance\n<"));
+        assert!(both.contains(">\n-0.2\n<") && both.contains(">\n1.4\n<"));
+    }
+
+    // Model comparison: the harmonic and Morse runs of hydrogen are drawn in their own colors with a
+    // legend, and an element without Morse parameters is rejected
+    #[cfg(feature = "svg")]
+    #[test]
+    fn model_comparison_of_hydrogen() {
+        let runs = crate::sim::model_comparison("H", 300.0).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].2.times.len(), runs[1].2.times.len());
+        let mut svg = String::new();
+        draw_comparison_plot(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &runs, "Harmonic vs Morse (H)", &PlotOptions::default())
+            .unwrap();
+        assert!(svg.contains("Harmonic vs Morse (H)"));
+        assert!(svg.contains(">\nHarmonic\n<") && svg.contains(">\nMorse\n<"));
+        assert!(svg.contains("#0000FF") && svg.contains("#FF0000"));
+        assert!(crate::sim::model_comparison("Ar", 300.0).is_err());
+    }
+
+    // Displacement unit: percent of the equilibrium distance needs a positive equilibrium distance
+    #[test]
+    fn displacement_unit_conversion() {
+        assert_eq!(displacement_unit_factor("au", None).unwrap(), 1.0);
+        assert_eq!(displacement_unit_factor("percent", Some(2.0)).unwrap(), 50.0);
+        assert!((0.028 * displacement_unit_factor("percent", Some(1.4)).unwrap() - 2.0).abs() < 1e-12);
+        assert!(displacement_unit_factor("percent", None).is_err());
+        assert!(displacement_unit_factor("percent", Some(0.0)).is_err());
+        assert!(displacement_unit_factor("bohr", None).is_err());
+    }
+
+    // Displacement unit: in percent the axis is relabelled, and plotting needs the equilibrium distance
+    #[cfg(feature = "svg")]
+    #[test]
+    fn displacement_plot_in_percent() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_displacement_unit(String::from("percent"));
+        let svg = render_plot_svg(&result, &params, "displacement", 800, 400, &options, Some(1.4011), None).unwrap();
+        assert!(svg.contains(">\nDisplacement (% of r_eq)\n<"));
+        assert!(render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).is_err());
+    }
+
+    // Axis labels: energies around 1e-19 are labelled in scientific notation (automatically, or with
+    // the configured number of significant figures)
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_labels_tiny_energies() {
+        assert_eq!(format_axis_label(1.234e-19, 0), "1.23e-19");
+        assert_eq!(format_axis_label(1.234e-19, 4), "1.234e-19");
+        assert_eq!(format_axis_label(0.25, 0), "0.25");
+
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let mut result = crate::sim::simulate_molecule(&params).unwrap();
+        let scale = 1e-19 / result.total_energies[0];
+        for series in [&mut result.potential_energies, &mut result.kinetic_energies, &mut result.total_energies] {
+            series.iter_mut().for_each(|e| *e *= scale);
+        }
+        let mut options = PlotOptions::default();
+        let svg = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        let labels = svg.matches("e-20\n<").count() + svg.matches("e-19\n<").count();
+        assert!(labels >= 3, "{}", labels);
+        options.set_significant_figures(4);
+        let svg = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert!(svg.contains(">\n1.000e-19\n<") || svg.contains("0e-20\n<"));
+    }
+
+    // Low temperature: at T = 1e-10 K the run finishes with a warning about the unresolvable amplitude
+    // instead of NaNs, and both plots still lay out their axes
+    #[cfg(feature = "svg")]
+    #[test]
+    fn tiny_temperature_warns_and_still_plots() {
+        for model in ["harmonic", "morse"] {
+            let params = SimulationParameters::new(model.to_string(), String::from("H"), 100.0, 0.1, 1e-10);
+            let result = crate::sim::simulate_molecule(&params).unwrap();
+            assert!(result.warnings.iter().any(|w| w.contains("below the numerical resolution")), "{:?}", result.warnings);
+            for series in [&result.displacements, &result.velocities, &result.potential_energies, &result.kinetic_energies, &result.total_energies] {
+                assert!(series.iter().all(|v| v.is_finite()));
+            }
+            for plot in ["energy", "displacement"] {
+                render_plot_svg(&result, &params, plot, 800, 400, &PlotOptions::default(), None, None).unwrap();
+            }
+        }
+    }
+
+    // Energy exchange plot: one (PE, KE) point per sample along with the conservation line, and a
+    // result without samples is rejected rather than laid out over an infinite range
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_exchange_plot_of_a_harmonic_run() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 100.0, 0.5, 300.0);
+        let mut result = crate::sim::simulate_molecule(&params).unwrap();
+        assert_eq!(result.potential_energies.len(), result.times.len());
+        assert_eq!(result.kinetic_energies.len(), result.times.len());
+        let mut svg = String::new();
+        draw_energy_exchange_plot(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &result, &params, &PlotOptions::default())
+            .unwrap();
+        assert!(svg.contains(">\nTrajectory\n<") && svg.contains(">\nKE + PE = E0\n<"));
+        let trajectory = svg.split("<polyline").find(|line| line.contains("stroke=\"#0000FF\"")).unwrap();
+        let points = trajectory.split("points=\"").nth(1).unwrap().split('"').next().unwrap();
+        assert_eq!(points.split_whitespace().count(), result.times.len());
+
+        result.kinetic_energies.pop();
+        let mut svg = String::new();
+        assert!(draw_energy_exchange_plot(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &result, &params, &PlotOptions::default())
+            .is_err());
+        result.potential_energies.clear();
+        result.kinetic_energies.clear();
+        let mut svg = String::new();
+        let error = draw_energy_exchange_plot(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), &result, &params, &PlotOptions::default())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Energy exchange plot needs at least one sample");
+    }
+
+    // Energy series selection: with only the total energy shown, the other series and their legend
+    // entries are skipped
+    #[cfg(feature = "svg")]
+    #[test]
+    fn energy_plot_with_only_the_total_energy() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_show_potential(false);
+        options.set_show_kinetic(false);
+        let svg = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert!(svg.contains(">\nTotal Energy\n<"));
+        assert!(!svg.contains(">\nPotential Energy\n<") && !svg.contains(">\nKinetic Energy\n<"));
+        assert!(svg.contains("#00FF00"));
+        assert!(!svg.contains("stroke=\"#FF0000\"") && !svg.contains("stroke=\"#0000FF\""));
+    }
+
+    // Thermal envelope: the ±sqrt(2 k_B T / k) lines bound the harmonic trajectory and get a legend
+    // entry, and enabling them without the amplitude is an error
+    #[cfg(feature = "svg")]
+    #[test]
+    fn displacement_plot_with_the_thermal_envelope() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let amplitude = crate::sim::thermal_amplitude(&params).unwrap();
+        let peak = result.displacements.iter().fold(0.0, |a: f64, &d| a.max(d.abs()));
+        assert!((peak / amplitude - 1.0).abs() < 1e-2, "{} vs {}", peak, amplitude);
+
+        let mut options = PlotOptions::default();
+        options.set_show_thermal_envelope(true);
+        let svg = render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, Some(amplitude)).unwrap();
+        assert!(svg.contains(">\nThermal amplitude\n<"));
+        let plain = render_plot_svg(&result, &params, "displacement", 800, 400, &PlotOptions::default(), None, None).unwrap();
+        let dashes = |svg: &str| svg.matches("opacity=\"0.6\" stroke=\"#000000\"").count();
+        assert!(dashes(&svg) > dashes(&plain) + 2, "{} vs {}", dashes(&svg), dashes(&plain));
+        assert!(render_plot_svg(&result, &params, "displacement", 800, 400, &options, None, None).is_err());
+    }
+
+    // Dual-axis plot: the displacement and total energy series are both drawn with their legend entries
+    // and axis descriptions, over the selected x-axis and fixed ranges, and an empty result is rejected
+    #[cfg(feature = "svg")]
+    #[test]
+    fn displacement_energy_dual_draws_both_series() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+        let mut result = crate::sim::simulate_molecule(&params).unwrap();
+        let draw = |result: &SimulationResult, options: &PlotOptions| {
+            let mut svg = String::new();
+            draw_displacement_energy_dual(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), result, &params, options)
+                .map(|_| svg)
+        };
+        let svg = draw(&result, &PlotOptions::default()).unwrap();
+        assert!(svg.contains(">\nDisplacement\n<") && svg.contains(">\nTotal Energy\n<") && svg.contains(">\nTime\n<"));
+        assert!(svg.contains("stroke=\"#0000FF\"") && svg.contains("stroke=\"#00FF00\""));
+
+        let mut options = PlotOptions::default();
+        options.set_x_axis(String::from("steps"));
+        options.set_displacement_y_max(Some(0.5));
+        options.set_energy_y_max(Some(0.01));
+        let svg = draw(&result, &options).unwrap();
+        assert!(svg.contains(">\nStep\n<") && svg.contains(">\n1000\n<"));
+        assert!(svg.contains(">\n0.5\n<") && svg.contains(">\n0.01\n<"));
+
+        result.total_energies.clear();
+        assert!(draw(&result, &PlotOptions::default()).is_err());
+    }
+
+    // Auto caption: both plots are captioned with the element, model, temperature and timestep of the run
+    // instead of their fixed titles
+    #[cfg(feature = "svg")]
+    #[test]
+    fn auto_caption_names_the_element_and_temperature() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_auto_caption(true);
+        for (plot, title) in [("energy", "Energy Over Time"), ("displacement", "Displacement Over Time")] {
+            let svg = render_plot_svg(&result, &params, plot, 800, 400, &options, None, None).unwrap();
+            assert!(svg.contains(">\nH, Morse, T=300 K, dt=0.5\n<"), "{}", plot);
+            assert!(!svg.contains(title));
+        }
+    }
+
+    // Steps x-axis: both plots label the x-axis "Step" and span the step indices 0..num_steps
+    // (the same for two runs of the same number of steps at different timesteps)
+    #[cfg(feature = "svg")]
+    #[test]
+    fn plots_with_a_steps_x_axis() {
+        let mut options = PlotOptions::default();
+        options.set_x_axis(String::from("steps"));
+        for timestep in [0.5, 0.25] {
+            let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 1000.0 * timestep, timestep, 300.0);
+            let result = crate::sim::simulate_molecule(&params).unwrap();
+            for plot in ["energy", "displacement"] {
+                let svg = render_plot_svg(&result, &params, plot, 800, 400, &options, None, None).unwrap();
+                assert!(svg.contains(">\nStep\n<") && !svg.contains(">\nTime\n<"), "{}", plot);
+                assert!(svg.contains(">\n1000\n<"), "{} {}", plot, timestep);
+            }
+        }
+        options.set_x_axis(String::from("frames"));
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 100.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        assert!(render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).is_err());
+    }
+
+    // Stacked energies: a hidden potential or kinetic energy is left out of the stack (and its legend)
+    #[cfg(feature = "svg")]
+    #[test]
+    fn stacked_energies_honour_hidden_series() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let mut options = PlotOptions::default();
+        options.set_stacked_energies(true);
+        options.set_show_potential(false);
+        let kinetic_only = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert_eq!(kinetic_only.matches("<polygon").count(), 1);
+        assert!(kinetic_only.contains(">\nKinetic Energy\n<") && !kinetic_only.contains(">\nPotential Energy\n<"));
+        options.set_show_potential(true);
+        options.set_show_kinetic(false);
+        let potential_only = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert_eq!(potential_only.matches("<polygon").count(), 1);
+        assert!(potential_only.contains(">\nPotential Energy\n<") && !potential_only.contains(">\nKinetic Energy\n<"));
+        options.set_show_potential(false);
+        let neither = render_plot_svg(&result, &params, "energy", 800, 400, &options, None, None).unwrap();
+        assert_eq!(neither.matches("<polygon").count(), 0);
+    }
+
+    // Comparison plot: single-sample runs still get a non-zero-width x-axis, the x-axis follows
+    // options.x_axis(), and no runs at all is an error
+    #[cfg(feature = "svg")]
+    #[test]
+    fn comparison_plot_axes() {
+        let runs: Vec<ComparisonRun> = ["harmonic", "morse"].into_iter().zip(["Harmonic", "Morse"])
+            .map(|(model, label)| {
+                let params = SimulationParameters::new(model.to_string(), String::from("H"), 0.0, 0.5, 300.0);
+                let result = crate::sim::simulate_molecule(&params).unwrap();
+                (label, params, result)
+            })
+            .collect();
+        let draw = |runs: &[ComparisonRun], options: &PlotOptions| {
+            let mut svg = String::new();
+            draw_comparison_plot(SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area(), runs, "Single samples", options)
+                .map(|_| svg)
+        };
+        let svg = draw(&runs, &PlotOptions::default()).unwrap();
+        assert!(svg.contains(">\nTime\n<") && svg.contains(">\n0.5\n<"));
+        let mut options = PlotOptions::default();
+        options.set_x_axis(String::from("steps"));
+        let svg = draw(&runs, &options).unwrap();
+        assert!(svg.contains(">\nStep\n<") && !svg.contains(">\nTime\n<"));
+        assert!(matches!(draw(&[], &PlotOptions::default()), Err(SimError::InvalidInput(_))));
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+    use web_sys::{OffscreenCanvas, OffscreenCanvasRenderingContext2d};
+    use crate::offscreen::OffscreenCanvasBackend;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    // Canvas lookup: an element that is not a <canvas> is reported with its tag name
+    #[wasm_bindgen_test]
+    fn non_canvas_element_is_reported() {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let div = document.create_element("div").unwrap();
+        div.set_id("not-a-canvas");
+        document.body().unwrap().append_child(&div).unwrap();
+        let error = canvas_backend("not-a-canvas", &PlotOptions::default()).err().unwrap();
+        assert_eq!(error.as_string().unwrap(), "Element 'not-a-canvas' is not a canvas (found div)");
+        let error = canvas_backend("no-such-element", &PlotOptions::default()).err().unwrap();
+        assert!(error.as_string().unwrap().contains("no-such-element"));
+    }
+
+    // Offscreen rendering: both plots draw on an OffscreenCanvas (no document lookup), leaving
+    // non-white pixels on it
+    #[wasm_bindgen_test]
+    fn plots_render_to_an_offscreen_canvas() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = crate::sim::simulate_molecule(&params).unwrap();
+        let options = PlotOptions::default();
+        let energy_canvas = OffscreenCanvas::new(800, 400).unwrap();
+        let displacement_canvas = OffscreenCanvas::new(800, 400).unwrap();
+        let errors = draw_plots(
+            OffscreenCanvasBackend::new(energy_canvas.clone()).map_err(|e| e.to_string()),
+            OffscreenCanvasBackend::new(displacement_canvas.clone()).map_err(|e| e.to_string()),
+            Ok((None, None)),
+            &result, &params, &options, &RenderToken::begin()
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        for (plot, canvas) in [("energy", energy_canvas), ("displacement", displacement_canvas)] {
+            let context: OffscreenCanvasRenderingContext2d = canvas.get_context("2d").unwrap().unwrap().unchecked_into();
+            let pixels = context.get_image_data(0.0, 0.0, 800.0, 400.0).unwrap().data();
+            assert!(pixels.chunks(4).any(|rgba| rgba[..3] != [255, 255, 255]), "{}", plot);
+        }
+    }
+}
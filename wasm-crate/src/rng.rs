@@ -0,0 +1,45 @@
+/*
+Module for seedable random numbers (used for randomized thermal initial conditions)
+
+Contains:
+ - Rng struct:
+    - small xorshift64* generator seeded through SplitMix64, so equal seeds give equal sequences
+    - uniform and standard normal (Box-Muller) draws
+*/
+
+// Small seedable pseudo-random number generator
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Scramble the seed (SplitMix64) so nearby seeds give unrelated sequences
+        let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^= z >> 31;
+        // xorshift must not be seeded with zero
+        Rng { state: if z == 0 { 0x9E3779B97F4A7C15 } else { z } }
+    }
+
+    // Returns the next raw 64-bit value (xorshift64*)
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    // Returns a uniform draw in [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Returns a standard normal draw (Box-Muller transform)
+    pub fn normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.uniform();  // in (0, 1], avoids ln(0)
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
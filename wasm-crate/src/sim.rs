@@ -4,44 +4,224 @@ Modules for simulation of diatomic molecules
 Contains:
  - ElementProperties struct: 
     - predefined elements (e.g. H, Hg, Ar) and their properties
+ - ElementConstants struct:
+    - public, serializable copy of an element's properties (for inspection)
+ - ElementInfo struct:
+    - descriptive metadata for each element (category and the models suited to it)
+ - is_supported_pair function:
+    - whether two atoms form a diatomic with parameters in the table (only homonuclear pairs so far)
+ - harmonic_residuals function:
+    - difference between the integrated and the analytic harmonic trajectory over time
+ - harmonic_potential_curves function:
+    - harmonic potentials of several elements over a shared displacement range (for comparing bond stiffness)
+ - MorseParameters struct / morse_from_spectroscopic function:
+    - Morse parameters from the spectroscopic constants ω_e and ω_e x_e (simulate_morse_spectroscopic runs them)
+ - time_reverse_check function:
+    - displacement error of a forward run followed by the same run with reversed velocity
  - SimulationParameters struct:
     - parameters for running a simulation, such as model type, element, duration, timestep, and temperature
+    - optional settings (e.g. timing, a reflecting wall, seeded random initial conditions) are set through setters after construction
  - SimulationState struct:
     - current state of the simulation, including time, displacement, force, acceleration, velocity, and energies
+    - generic over the floating-point type (f32 by default; precision "f64" integrates in double precision)
+    - initialized per model from the temperature, or randomly from the Maxwell-Boltzmann distribution
+    - or (init_mode "thermal_velocity") at the potential minimum with the thermal speed sqrt(k_B T / m)
+    - initial_state function returns it without running the simulation
  - SimulationResult struct:
-    - results of the simulation, including time series data for displacements, distances, and energies
+    - results of the simulation, including time series data for displacements, distances, velocities and energies
+    - summary quantities: RMS and maximum velocity, optionally the wall-clock compute time
+    - equipartition energy ½ k_B T (reference for the mean energies; equipartition_energy function in AU or SI)
+    - instantaneous virial (distance * force) series and its time average
+    - warnings about the run (e.g. an amplitude below numerical resolution at very low temperatures)
+    - can be sampled (uniformly, logarithmically in time or at turning points) or serialized to a JSON string
+      (optionally restricted to selected fields)
+    - reports the relative energy drift of the run
+    - normalized displacement autocorrelation over lag times
+    - can be flattened into one column-major buffer (ColumnLayout struct describes the columns)
+    - can be interpolated at any time (SampledState struct), also on non-uniformly sampled results
+    - turning points of the trajectory from the sign changes of the velocity
+    - number of full oscillations (two turning points each)
+    - peak displacement (largest distance from equilibrium)
+    - kinetic to potential energy ratio over time (NaN where the potential energy is zero)
+    - relaxation time: when the amplitude envelope decays to 1/e of its initial value
+    - energy series optionally smoothed by a centered moving average (smoothing_window),
+      keeping the raw series (EnergySeries struct)
+    - displacements from equilibrium, or (displacement_reference "initial") from the first sample
+    - initial displacement r0 of the initial conditions (initial_amplitude)
+    - model, element and temperature of the run, so each result describes itself
+    - output presets: "raw" (every step, atomic units) or "display" (downsampled, SI units,
+      energies from the dissociation limit)
+ - SimulationSummary struct:
+    - scalar statistics of a run (amplitude, frequency, mean energies, ...) without the time series
+ - Force laws:
+    - force and potential energy at a displacement for each model (harmonic, Morse, Lennard-Jones, Kratzer)
+    - Lennard-Jones generalized to the Mie n-6 potential with a configurable repulsive exponent (lj_exponent, 12 by default)
+    - is_anharmonic function tells whether a model's potential deviates from the harmonic parabola
  - simulate_molecule function:
     - orchestrates the simulation process by selecting the appropriate model based on parameters
-    - calls one of:
-        - simulate_harmonic_oscillator function
-        - simulate_morse_potential function
-        - simulate_lennard_jones function
+    - simulate_continue function continues a previous run from its last sample (not from a subsampled result)
+    - integrates the selected model with the Velocity Verlet algorithm (simulate_model function)
+    - optionally splits each recorded timestep into smaller integration substeps
+    - optionally (exact_duration) ends with a partial step so the last time equals the duration
+ - step_count function:
+    - validates the duration and timestep and checks (or optionally caps) the number of steps before allocating
+    - notes adjustments (capped steps, a duration that is not a whole number of steps) as warnings
+ - rescale_to_energy function:
+    - velocity-rescaling thermostat, used for the optional temperature ramp
+ - reflect_at_wall function:
+    - optional hard, elastic reflecting wall applied after each step
+    - also used for the hard core (hard_core), a wall at zero interatomic distance
+ - now_ms function:
+    - reads the high-resolution clock of the global `performance` object (browser or Node.js, no DOM)
+      for the optional timing of the integration loop (the system clock on native targets)
+ - measure_period function:
+    - integrates until one full oscillation is complete and returns its period (from either initial condition mode)
+    - steps like the run itself (precision, substeps, reflecting wall and hard core)
+ - classical_action function:
+    - action ∮ p dq of the trajectory over one measured period
+ - anharmonicity function:
+    - fractional drop in oscillation frequency between a small and the given amplitude
+ - EnsembleResult struct / ensemble_average function:
+    - mean and standard deviation of the energies over many random thermal trajectories
+ - Correlation struct / autocorrelation function:
+    - normalized displacement autocorrelation of a run against lag time
+ - timestep_convergence function:
+    - runs the simulation at several timesteps and reports the energy drift of each
+ - amplitude_vs_temperature function:
+    - runs the simulation at several temperatures and reports the peak displacement of each
+ - IsotopeResult struct / isotope_comparison function:
+    - runs an element's potential with several substituted masses
+ - LennardJonesParameters struct / interpolate_lennard_jones function:
+    - illustrative eps and r* for an arbitrary mass, interpolated in log(mass) between tabulated elements
+ - timestep_for_resolution function:
+    - timestep giving a number of steps per small-oscillation period of a model
+ - max_stable_timestep function:
+    - largest accurate timestep, from the largest curvature of the potential between the turning points
+ - model_comparison function:
+    - runs the harmonic and Morse models for the same element over a few harmonic periods
+ - well_depth and equilibrium_distance functions:
+    - well depth of the selected model and equilibrium distance of the element
+ - is_bound function:
+    - whether the initial total energy is below the well depth (no dissociation)
+ - dissociation_fraction function:
+    - illustrative Boltzmann estimate exp(-D / k_B T) of the dissociated fraction (Morse elements)
+ - thermal_amplitude function:
+    - amplitude sqrt(2 k_B T / k) of the harmonic model at the given temperature
+ - vibrational_wavenumber function:
+    - harmonic vibrational wavenumber (cm⁻¹) of the harmonic and Morse models
+ - force_curve function:
+    - samples the force of the selected model over a range of displacements
+ - turning_points function:
+    - the two displacements where the potential equals the initial total energy
+ - tests module (native only):
+    - energy conservation of the Verlet integration for each model, with per-model tolerances
+    - initial state against the first sample of a run
+    - "did you mean" suggestions for unknown elements
+    - virial theorem for the harmonic model
+    - Lennard-Jones interpolation at a tabulated mass
+    - energy drift of the velocity Verlet step against the earlier midpoint scheme
+    - continued runs against one long run
+    - initial energies of the thermal velocity mode
+    - timestep for a resolution against the measured period
+    - turning points of a harmonic run
+    - amplitude growth under a heating ramp
+    - oscillation count of a run of about 5 periods
+    - Lennard-Jones force against the numerical gradient of its potential
+    - energy drift of f64 against f32 precision
+    - moving average of constant and noisy series
+    - equipartition energy against ½ k_B T
+    - distances of a run with the hard core
+    - relaxation time of a cooled and an undamped run
+    - Morse parameters from the spectroscopic constants of H2
+    - time reversibility of the Verlet integration
+    - dissociation fraction against temperature for H
+    - displacements measured from the initial position
+    - Lennard-Jones initial state at an overflowing temperature
+    - "raw" against "display" preset outputs of the same run
+    - supported homonuclear and unsupported heteronuclear pairs
+    - anharmonicity of each model
+    - initial amplitude against the first displacement sample
+    - model, element and temperature echoed in the result (and its JSON)
+    - peak displacement growing with temperature
+    - classical action of the harmonic oscillator against E / ν
+    - simulation without any JavaScript (DOM) access
+    - energy drift at the stable timestep limit against 5 times that timestep
+    - harmonic residuals growing over the run
+    - final time of a run whose duration is not a whole number of timesteps
+    - Mie exponent 12 against the standard Lennard-Jones run, and a softer wall
+    - kinetic to potential energy ratio of a harmonic run
+    - JSON export parsed back into a result
+    - Kratzer minimum at the equilibrium distance of H, and a low-temperature run
+    - measured harmonic period against 2π / ω for both initial condition modes
+    - compute time with and without timing
+    - classical turning points of the harmonic and Morse potentials
+    - energy drift over decreasing timesteps
+    - harmonic force constant of zero
+    - RMS and maximum velocity of a thermal harmonic run
+    - valid models and categories of the element info
+    - run summary against the full time series
+    - velocity reversal and energy at a reflecting wall
+    - Morse and Lennard-Jones well depths and the equilibrium distance
+    - seeded random initial conditions
+    - ensemble mean kinetic energy against the equipartition value
+    - too many steps for a tiny timestep
+    - force curves of the harmonic, Morse and Lennard-Jones models
+    - serialized constants of hydrogen against the table
+    - vibrational wavenumber of hydrogen
+    - Morse anharmonicity at two temperatures
+    - flat column buffer against the serialized series
+    - sample spacing of the uniform, log and turning-point sampling strategies
+    - displacement autocorrelation of a harmonic run
+    - warning for a capped step count
+    - JSON with only the requested fields
+    - isotope substitution frequencies
+    - bound and unbound hydrogen in the Morse well
+    - sample_at and Lennard-Jones parameters interpolated at a midpoint
+    - unseeded random runs on native targets
+    - continuation of a subsampled and of a raw run above the sample limit
+    - harmonic residuals of an unseeded random start
+    - Kratzer initial state above the well depth
+    - measured period with a wall, substeps and f64, and at rest at the minimum
 */
 
 use wasm_bindgen::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SimError;
+use crate::float::Float;
+use crate::rng::Rng;
 
 // Conversion factors and constants
-const KB: f32 = 1.3806488E-23;
+pub(crate) const KB: f32 = 1.3806488E-23;
+pub(crate) const HARTREE_TO_J: f32 = 4.359744E-18;
+#[allow(clippy::excessive_precision)]
 const A0_TO_M: f32 = 5.2917721092E-11;
+const ELECTRON_MASS_KG: f64 = 9.10938291E-31;
+const SPEED_OF_LIGHT_CM_PER_S: f64 = 2.99792458E+10;
+const HARTREE_PER_WAVENUMBER: f64 = 4.556335253E-06;
+#[allow(clippy::excessive_precision)]
+const AU_TIME_TO_S: f64 = 2.418884326505E-17;
 
 
 
 // Structure to hold physical constants for each element
 #[derive(Clone, Copy)]
 pub struct ElementProperties {
-    m_au: f32,      // Mass (atomic units)
+    pub(crate) m_au: f32, // Mass (atomic units)
     k_au: f32,      // Force constant (atomic units)
     k_si: f32,      // Force constant (SI)
     d_au: f32,      // Dissociation energy (atomic units)
     d_si: f32,      // Dissociation energy (SI)
     alpha_au: f32,  // Bond strength (atomic units)
     alpha_si: f32,  // Bond strength (SI)
-    rstr_au: f32,
-    eps_au: f32,
+    pub(crate) rstr_au: f32, // Equilibrium separation (atomic units)
+    eps_au: f32,    // Lennard-Jones well depth (atomic units)
+    lj_exponent: u32, // Repulsive exponent n of the Lennard-Jones (Mie n-6) potential
 }
 
 // Define constants for all supported elements
+// (values are kept exactly as tabulated, even where they exceed f32 precision)
+#[allow(clippy::excessive_precision)]
 const ELEMENT_PROPERTIES: &[(&str, ElementProperties)] = &[
     // Hydrogen
     ("H", ElementProperties {
@@ -52,8 +232,9 @@ const ELEMENT_PROPERTIES: &[(&str, ElementProperties)] = &[
         d_si: 7.928147E-19,
         alpha_au: 1.003894E+00,
         alpha_si: 1.897085E+10,
-        rstr_au: 0.0,
+        rstr_au: 1.401100E+00,
         eps_au: 0.0,
+        lj_exponent: 12,
     }),
     // Mercury
     ("Hg", ElementProperties {
@@ -66,6 +247,7 @@ const ELEMENT_PROPERTIES: &[(&str, ElementProperties)] = &[
         alpha_si: 0.0,
         rstr_au: 6.952302E+00,
         eps_au: 1.845314E-03,
+        lj_exponent: 12,
     }),
     // Argon
     ("Ar", ElementProperties {
@@ -78,26 +260,139 @@ const ELEMENT_PROPERTIES: &[(&str, ElementProperties)] = &[
         alpha_si: 0.0,
         rstr_au: 7.107260E+00,
         eps_au: 4.536240E-04,
+        lj_exponent: 12,
     }),
 ];
 
 // Helper function to get element properties
-fn get_element_properties(element: &str) -> Result<ElementProperties, JsValue> {
+fn get_element_properties(element: &str) -> Result<ElementProperties, SimError> {
     ELEMENT_PROPERTIES
         .iter()
         .find(|(symbol, _)| *symbol == element)
         .map(|(_, props)| *props)
-        .ok_or_else(|| JsValue::from_str("Element not supported"))
+        .ok_or_else(|| unknown_element(element))
+}
+
+// Helper function to build the unknown-element error, suggesting the closest supported symbol
+// (by case-insensitive edit distance, only when fewer edits than the symbol has characters)
+fn unknown_element(element: &str) -> SimError {
+    let lowercase = element.to_lowercase();
+    let suggestion = ELEMENT_PROPERTIES
+        .iter()
+        .map(|(symbol, _)| (edit_distance(&lowercase, &symbol.to_lowercase()), *symbol))
+        .min_by_key(|&(distance, _)| distance)
+        .filter(|&(distance, _)| distance < element.chars().count())
+        .map(|(_, symbol)| symbol.to_string());
+    SimError::UnknownElement { element: element.to_string(), suggestion }
+}
+
+// Helper function to count the single-character insertions, deletions and substitutions turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Public, serializable mirror of ElementProperties (for inspecting the constants from JavaScript)
+#[derive(Serialize)]
+pub struct ElementConstants {
+    pub symbol: String,
+    pub m_au: f32,
+    pub k_au: f32,
+    pub k_si: f32,
+    pub d_au: f32,
+    pub d_si: f32,
+    pub alpha_au: f32,
+    pub alpha_si: f32,
+    pub rstr_au: f32,
+    pub eps_au: f32,
+}
+
+// Function to get all the constants of an element
+pub fn get_element_constants(element: &str) -> Result<ElementConstants, SimError> {
+    let properties = get_element_properties(element)?;
+    Ok(ElementConstants {
+        symbol: element.to_string(),
+        m_au: properties.m_au,
+        k_au: properties.k_au,
+        k_si: properties.k_si,
+        d_au: properties.d_au,
+        d_si: properties.d_si,
+        alpha_au: properties.alpha_au,
+        alpha_si: properties.alpha_si,
+        rstr_au: properties.rstr_au,
+        eps_au: properties.eps_au,
+    })
+}
+
+// Structure to hold descriptive metadata for each element
+#[derive(Serialize)]
+pub struct ElementInfo {
+    pub symbol: &'static str,                     // Element symbol
+    pub category: &'static str,                   // Kind of diatomic (e.g. "noble_gas", "homonuclear_diatomic")
+    pub valid_models: &'static [&'static str],    // Models suited to this element
 }
 
+// Define metadata for all supported elements
+// (categories are "noble_gas", "homonuclear_diatomic" or "heteronuclear_diatomic"; closed-shell Hg is,
+// like the noble gases, only van der Waals bound and grouped with them)
+const ELEMENT_INFO: &[ElementInfo] = &[
+    ElementInfo { symbol: "H", category: "homonuclear_diatomic", valid_models: &["harmonic", "morse", "kratzer"] },
+    ElementInfo { symbol: "Hg", category: "noble_gas", valid_models: &["lennard-jones"] },
+    ElementInfo { symbol: "Ar", category: "noble_gas", valid_models: &["lennard-jones"] },
+];
+
+// Helper function to get element metadata
+pub fn get_element_info(element: &str) -> Result<&'static ElementInfo, SimError> {
+    ELEMENT_INFO
+        .iter()
+        .find(|info| info.symbol == element)
+        .ok_or_else(|| unknown_element(element))
+}
+
+// Function to check whether the table has parameters for the diatomic of atoms `a` and `b`
+// (the parameters are per homonuclear molecule, so only two atoms of the same supported element qualify)
+pub fn is_supported_pair(a: &str, b: &str) -> bool {
+    a == b && get_element_properties(a).is_ok()
+}
+
+// Default limit on the number of integration steps of a run
+const DEFAULT_MAX_STEPS: f64 = 5.0E+07;
+
 // Define parameter struct for simulation settings
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct SimulationParameters {
-    model: String,     // Model type (e.g., "harmonic", "morse", "lennard-jones")
+    model: String,     // Model type (e.g., "harmonic", "morse", "lennard-jones", "kratzer")
     element: String,   // Element symbol (e.g., "H", "Hg", "Ar")
     duration: f64,     // Duration of the simulation
     timestep: f64,     // Time step for the simulation
     temperature: f64,  // Temperature for the simulation
+    timing: bool,      // Whether to time the integration loop (wall-clock)
+    reflect_at: Option<f64>, // Displacement of an optional hard (elastic) reflecting wall
+    hard_core: bool,         // Whether a reflecting wall at zero interatomic distance stops the bond compressing through zero
+    thermal_random: bool,    // Whether to draw random (Maxwell-Boltzmann) initial conditions
+    seed: Option<u64>,       // Seed for the random initial conditions (random if not set)
+    max_steps: f64,          // Largest number of integration steps allowed (guards against huge allocations)
+    sampling: String,        // Which samples are kept in the result ("uniform", "log", "turning_points")
+    cap_steps: bool,         // Whether to cap an over-long run at max_steps (with a warning) instead of rejecting it
+    substeps: u32,           // Verlet updates per recorded timestep (each of timestep / substeps)
+    init_mode: String,       // Initial condition ("thermal_displacement": at rest, displaced; "thermal_velocity": at the minimum, moving)
+    temperature_ramp: Option<(f64, f64)>, // Thermostat target temperature ramped linearly from start to end over the run
+    precision: String,       // Floating-point type of the integration ("f32": fast, "f64": accurate); results are always f64
+    smoothing_window: Option<usize>, // Samples in the centered moving average of the returned energy series (no smoothing if not set)
+    displacement_reference: String,  // Origin of the returned displacements ("equilibrium", "initial": the first sample)
+    preset: Option<String>,          // Bundle of output settings ("raw", "display") replacing the individual ones (see SimulationResult::finish)
+    lj_exponent: Option<u32>,        // Repulsive exponent n of the Lennard-Jones model (Mie n-6 potential; 12 if not set)
+    exact_duration: bool,            // Whether a final partial step makes the run end exactly at the duration
 }
 
 #[wasm_bindgen]
@@ -110,6 +405,23 @@ impl SimulationParameters {
             duration,
             timestep,
             temperature,
+            timing: false,
+            reflect_at: None,
+            hard_core: false,
+            thermal_random: false,
+            seed: None,
+            max_steps: DEFAULT_MAX_STEPS,
+            sampling: String::from("uniform"),
+            cap_steps: false,
+            substeps: 1,
+            init_mode: String::from("thermal_displacement"),
+            precision: String::from("f32"),
+            temperature_ramp: None,
+            smoothing_window: None,
+            displacement_reference: String::from("equilibrium"),
+            preset: None,
+            exact_duration: false,
+            lj_exponent: None,
         }
     }
     
@@ -138,22 +450,230 @@ impl SimulationParameters {
     pub fn temperature(&self) -> f64 {
         self.temperature
     }
+    
+    #[wasm_bindgen(getter)]
+    pub fn timing(&self) -> bool {
+        self.timing
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn reflect_at(&self) -> Option<f64> {
+        self.reflect_at
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn hard_core(&self) -> bool {
+        self.hard_core
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn thermal_random(&self) -> bool {
+        self.thermal_random
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn max_steps(&self) -> f64 {
+        self.max_steps
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn sampling(&self) -> String {
+        self.sampling.clone()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn cap_steps(&self) -> bool {
+        self.cap_steps
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn substeps(&self) -> u32 {
+        self.substeps
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn init_mode(&self) -> String {
+        self.init_mode.clone()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn precision(&self) -> String {
+        self.precision.clone()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn smoothing_window(&self) -> Option<usize> {
+        self.smoothing_window
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn displacement_reference(&self) -> String {
+        self.displacement_reference.clone()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn preset(&self) -> Option<String> {
+        self.preset.clone()
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn exact_duration(&self) -> bool {
+        self.exact_duration
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn lj_exponent(&self) -> Option<u32> {
+        self.lj_exponent
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn temperature_ramp_start(&self) -> Option<f64> {
+        self.temperature_ramp.map(|(start, _)| start)
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn temperature_ramp_end(&self) -> Option<f64> {
+        self.temperature_ramp.map(|(_, end)| end)
+    }
+    
+    // Setters for the optional settings
+    #[wasm_bindgen(setter)]
+    pub fn set_timing(&mut self, timing: bool) {
+        self.timing = timing;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_reflect_at(&mut self, reflect_at: Option<f64>) {
+        self.reflect_at = reflect_at;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_hard_core(&mut self, hard_core: bool) {
+        self.hard_core = hard_core;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_thermal_random(&mut self, thermal_random: bool) {
+        self.thermal_random = thermal_random;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_max_steps(&mut self, max_steps: f64) {
+        self.max_steps = max_steps;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_sampling(&mut self, sampling: String) {
+        self.sampling = sampling;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_cap_steps(&mut self, cap_steps: bool) {
+        self.cap_steps = cap_steps;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_substeps(&mut self, substeps: u32) {
+        self.substeps = substeps;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_init_mode(&mut self, init_mode: String) {
+        self.init_mode = init_mode;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_precision(&mut self, precision: String) {
+        self.precision = precision;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_smoothing_window(&mut self, smoothing_window: Option<usize>) {
+        self.smoothing_window = smoothing_window;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_displacement_reference(&mut self, displacement_reference: String) {
+        self.displacement_reference = displacement_reference;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_preset(&mut self, preset: Option<String>) {
+        self.preset = preset;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_exact_duration(&mut self, exact_duration: bool) {
+        self.exact_duration = exact_duration;
+    }
+    
+    #[wasm_bindgen(setter)]
+    pub fn set_lj_exponent(&mut self, lj_exponent: Option<u32>) {
+        self.lj_exponent = lj_exponent;
+    }
+    
+    // Ramp the temperature from `start` to `end` over the run (the run starts at `start`,
+    // and a thermostat keeps the total energy at k_B T of the ramped temperature)
+    pub fn set_temperature_ramp(&mut self, start: f64, end: f64) {
+        self.temperature_ramp = Some((start, end));
+    }
+    
+    pub fn clear_temperature_ramp(&mut self) {
+        self.temperature_ramp = None;
+    }
+}
+
+impl SimulationParameters {
+    // Temperature ramp as (start, end), if set
+    pub fn temperature_ramp(&self) -> Option<(f64, f64)> {
+        self.temperature_ramp
+    }
+
+    // Temperature the initial conditions are built from (the start of the ramp, if set)
+    pub fn initial_temperature(&self) -> f64 {
+        self.temperature_ramp.map_or(self.temperature, |(start, _)| start)
+    }
 }
 
 // Structure to represent the current state of the simulation
-#[derive(Clone)]
-pub struct SimulationState {
-    pub time: f32,          // Current time in the simulation
-    pub displacement: f32,  // Current displacement from equilibrium
-    pub force: f32,         // Current force acting on the molecule
-    pub acceleration: f32,  // Current acceleration of the molecule
-    pub velocity: f32,      // Current velocity of the molecule
-    pub kinetic_e: f32,     // Current kinetic energy
-    pub potential_e: f32,   // Current potential energy
-    pub total_e: f32,       // Total energy (kinetic + potential)
+// (`F` is the floating-point type of the integration)
+#[derive(Clone, Serialize)]
+pub struct SimulationState<F = f32> {
+    pub time: F,          // Current time in the simulation
+    pub displacement: F,  // Current displacement from equilibrium
+    pub force: F,         // Current force acting on the molecule
+    pub acceleration: F,  // Current acceleration of the molecule
+    pub velocity: F,      // Current velocity of the molecule
+    pub kinetic_e: F,     // Current kinetic energy
+    pub potential_e: F,   // Current potential energy
+    pub total_e: F,       // Total energy (kinetic + potential)
 }
 
 impl SimulationState {
+    // Converts the state to another floating-point type (e.g. to continue it in double precision)
+    pub(crate) fn convert<F: Float>(&self) -> SimulationState<F> {
+        SimulationState {
+            time: F::from_f32(self.time),
+            displacement: F::from_f32(self.displacement),
+            force: F::from_f32(self.force),
+            acceleration: F::from_f32(self.acceleration),
+            velocity: F::from_f32(self.velocity),
+            kinetic_e: F::from_f32(self.kinetic_e),
+            potential_e: F::from_f32(self.potential_e),
+            total_e: F::from_f32(self.total_e),
+        }
+    }
+
     // Initialize state for harmonic oscillator model
     pub fn init_harmonic_oscillator(properties: ElementProperties, temperature: f64) -> SimulationState {
         // Calculate the initial displacement based on temperature
@@ -197,45 +717,375 @@ impl SimulationState {
     }
 
     // Initialize state for Lennard-Jones potential model
-    pub fn init_lennard_jones(properties: ElementProperties, temperature: f64) -> SimulationState {
+    pub fn init_lennard_jones(properties: ElementProperties, temperature: f64) -> Result<SimulationState, SimError> {
         // Calculate initial displacements
         let r0_si_harm: f32 = ((2.0 * KB * temperature as f32) / properties.k_si).sqrt();
         let r0_a0_harm: f32 = r0_si_harm / A0_TO_M;
         
-        // Calculate LJ initial displacement from harmonic displacement
-        let r0_a0_lj: f32 = properties.rstr_au * (((2.0 * properties.eps_au).powf(1.0 / 12.0) * 
-                           ((properties.k_au).sqrt() * r0_a0_harm + 
-                           (2.0 * properties.eps_au).sqrt()).powf(-1.0 / 6.0)) - 1.0);
+        // Calculate LJ initial displacement from harmonic displacement (in closed form for the 12-6
+        // potential; other exponents bisect for V = ½ k r0² on the compressed side of the well)
+        let r0_a0_lj: f32 = if properties.lj_exponent == 12 {
+            properties.rstr_au * (((2.0 * properties.eps_au).powf(1.0 / 12.0) * 
+            ((properties.k_au).sqrt() * r0_a0_harm + 
+            (2.0 * properties.eps_au).sqrt()).powf(-1.0 / 6.0)) - 1.0)
+        } else {
+            let energy = 0.5 * properties.k_au * r0_a0_harm * r0_a0_harm;
+            let potential = |r: f32| lennard_jones_force(&properties, r).1;
+            bisect(&potential, energy, -properties.rstr_au * (1.0 - 1e-6), 0.0)
+        };
         
-        let rstar_over = properties.rstr_au / (r0_a0_lj + properties.rstr_au);
-        let init_force = (12.0 / (r0_a0_lj + properties.rstr_au)) * 
-                        properties.eps_au * (rstar_over.powi(12) - rstar_over.powi(6));
+        // (force and energy from the same force law as the integration loop)
+        let (init_force, init_potential) = lennard_jones_force(&properties, r0_a0_lj);
         
-        SimulationState {
+        // The displacement compresses the bond (between -r* and 0); at extreme temperatures the
+        // f32 expression overflows and gives a NaN or infinite energy instead
+        let in_well = r0_a0_lj.is_finite() && r0_a0_lj > -properties.rstr_au && r0_a0_lj <= 0.0;
+        if !(in_well && init_force.is_finite() && init_potential.is_finite()) {
+            return Err(SimError::InvalidInput(format!(
+                "Lennard-Jones initial displacement is not finite or outside the well at {} K (got {} Bohr, energy {})",
+                temperature, r0_a0_lj, init_potential
+            )));
+        }
+        
+        Ok(SimulationState {
             time: 0.0,
             displacement: r0_a0_lj,
             force: init_force,
             acceleration: init_force / properties.m_au,
             velocity: 0.0,
             kinetic_e: 0.0,
-            potential_e: properties.eps_au * (rstar_over.powi(12) - 2.0 * rstar_over.powi(6) + 1.0),
-            total_e: properties.eps_au * (rstar_over.powi(12) - 2.0 * rstar_over.powi(6) + 1.0),
+            potential_e: init_potential,
+            total_e: init_potential,
+        })
+    }
+
+    // Initialize state at the potential minimum moving with the thermal speed sqrt(k_B T / m)
+    // (kinetic energy k_B T / 2, no potential energy)
+    pub fn init_thermal_velocity(properties: ElementProperties, temperature: f64, force_law: &impl Fn(f32) -> (f32, f32)) -> SimulationState {
+        let kt_au = KB * temperature as f32 / HARTREE_TO_J;
+        let velocity = (kt_au / properties.m_au).sqrt();
+        let (force, potential) = force_law(0.0);
+        let kinetic = 0.5 * properties.m_au * velocity * velocity;
+        SimulationState {
+            time: 0.0,
+            displacement: 0.0,
+            force,
+            acceleration: force / properties.m_au,
+            velocity,
+            kinetic_e: kinetic,
+            potential_e: potential,
+            total_e: kinetic + potential,
+        }
+    }
+
+    // Initialize state with a random displacement and velocity from the Maxwell-Boltzmann distribution
+    // (displacement drawn in the harmonic approximation of the well, redrawn if the atoms would overlap)
+    pub fn init_thermal_random(properties: ElementProperties, temperature: f64, force_law: &impl Fn(f32) -> (f32, f32), rng: &mut Rng) -> SimulationState {
+        let kt_au = KB * temperature as f32 / HARTREE_TO_J;
+        let sigma_displacement = (kt_au / properties.k_au).sqrt();
+        let sigma_velocity = (kt_au / properties.m_au).sqrt();
+        
+        // Draw the displacement (at most a few attempts are ever needed)
+        let mut displacement = 0.0;
+        for _ in 0..1000 {
+            let r = sigma_displacement * rng.normal() as f32;
+            let (force, potential) = force_law(r);
+            let overlapping = properties.rstr_au > 0.0 && r <= -properties.rstr_au;
+            if force.is_finite() && potential.is_finite() && !overlapping {
+                displacement = r;
+                break;
+            }
+        }
+        let velocity = sigma_velocity * rng.normal() as f32;
+        
+        let (force, potential) = force_law(displacement);
+        let kinetic = 0.5 * properties.m_au * velocity * velocity;
+        SimulationState {
+            time: 0.0,
+            displacement,
+            force,
+            acceleration: force / properties.m_au,
+            velocity,
+            kinetic_e: kinetic,
+            potential_e: potential,
+            total_e: kinetic + potential,
+        }
+    }
+
+    // Initialize state for Kratzer potential model
+    pub fn init_kratzer(properties: ElementProperties, temperature: f64) -> SimulationState {
+        // Calculate initial displacements
+        let r0_si_harm: f32 = ((2.0 * KB * temperature as f32) / properties.k_si).sqrt();
+        let r0_a0_harm: f32 = r0_si_harm / A0_TO_M;
+        
+        // Stretch the bond until the Kratzer energy matches the harmonic energy
+        let e0_au = 0.5 * properties.k_au * r0_a0_harm.powi(2);
+        let r0_a0_kratzer: f32 = properties.rstr_au / (1.0 - (e0_au / properties.d_au).sqrt()) - properties.rstr_au;
+        
+        let re_over = properties.rstr_au / (r0_a0_kratzer + properties.rstr_au);
+        let init_force = -2.0 * properties.d_au * (1.0 - re_over) * re_over / (r0_a0_kratzer + properties.rstr_au);
+        
+        SimulationState {
+            time: 0.0,
+            displacement: r0_a0_kratzer,
+            force: init_force,
+            acceleration: init_force / properties.m_au,
+            velocity: 0.0,
+            kinetic_e: 0.0,
+            potential_e: properties.d_au * (1.0 - re_over).powi(2),
+            total_e: properties.d_au * (1.0 - re_over).powi(2),
         }
     }
 }
 
 // Define result struct for time series data
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct SimulationResult {
     pub times: Vec<f64>,             // Time points of the simulation
     pub displacements: Vec<f64>,     // Displacements at each time point
-    pub distances: Vec<f64>,         // Distances at each time point
+    pub distances: Vec<f64>,         // Interatomic distances (equilibrium distance + displacement) at each time point
+    pub velocities: Vec<f64>,        // Velocities at each time point
     pub potential_energies: Vec<f64>,// Potential energies at each time point
     pub kinetic_energies: Vec<f64>,  // Kinetic energies at each time point
     pub total_energies: Vec<f64>,    // Total energies at each time point
+    pub virial: Vec<f64>,            // Instantaneous virial (distance * force) at each time point
+    pub compute_ms: Option<f64>,     // Wall-clock time of the integration loop (ms), when timing is enabled
+    pub rms_velocity: f64,           // Root-mean-square velocity over the trajectory (atomic units)
+    pub max_velocity: f64,           // Maximum speed over the trajectory (atomic units)
+    pub mean_virial: f64,            // Time-averaged virial over the trajectory (virial theorem: -2 * mean kinetic energy for the harmonic model)
+    pub warnings: Vec<String>,       // Human-readable notes about the run (e.g. a numerically unresolvable amplitude)
+    #[serde(default)]
+    pub equipartition_energy: f64,   // Equipartition mean energy ½ k_B T per degree of freedom at the initial temperature (reference line)
+    #[serde(default)]
+    pub raw_energies: Option<EnergySeries>, // Energy series before smoothing (only when a smoothing window is set)
+    #[serde(default)]
+    pub displacement_offset: f64,    // Displacement subtracted from the stored displacements (non-zero with displacement_reference "initial")
+    #[serde(default)]
+    pub energy_offset: f64,          // Energy subtracted from the potential and total energies (the well depth with the "display" preset)
+    #[serde(default)]
+    pub si_units: bool,              // Whether the series are in SI units (s, m, m/s, J) instead of atomic units ("display" preset)
+    #[serde(default)]
+    pub initial_amplitude: f64,      // Initial displacement r0 computed by the initial conditions (from equilibrium, whatever the displacement reference)
+    #[serde(default)]
+    pub model: String,               // Model of the run (as in the parameters)
+    #[serde(default)]
+    pub element: String,             // Element of the run (as in the parameters)
+    #[serde(default)]
+    pub temperature: f64,            // Initial temperature of the run (K)
+}
+
+// Define struct for the potential, kinetic and total energy series
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnergySeries {
+    pub potential_energies: Vec<f64>,
+    pub kinetic_energies: Vec<f64>,
+    pub total_energies: Vec<f64>,
+}
+
+impl EnergySeries {
+    // Applies `f` to each of the series
+    fn map(self, f: impl Fn(Vec<f64>) -> Vec<f64>) -> EnergySeries {
+        EnergySeries {
+            potential_energies: f(self.potential_energies),
+            kinetic_energies: f(self.kinetic_energies),
+            total_energies: f(self.total_energies),
+        }
+    }
+}
+
+// Define summary struct of scalar statistics (no time series)
+#[derive(Serialize)]
+pub struct SimulationSummary {
+    pub n_samples: usize,              // Number of stored time points
+    pub final_time: f64,               // Last time point
+    pub amplitude: f64,                // Half of the peak-to-peak displacement
+    pub frequency: Option<f64>,        // Oscillation frequency from mean crossings (None if less than a period)
+    pub mean_potential_energy: f64,    // Time-averaged potential energy
+    pub mean_kinetic_energy: f64,      // Time-averaged kinetic energy
+    pub mean_total_energy: f64,        // Time-averaged total energy
+    pub energy_drift: f64,             // Relative energy drift (see SimulationResult::energy_drift)
+    pub rms_velocity: f64,             // Root-mean-square velocity
+    pub max_velocity: f64,             // Maximum speed
 }
 
 impl SimulationResult {
+    pub fn sample(self, strategy: &str, threshold_samples: usize, target_samples: usize) -> Result<SimulationResult, SimError> {
+        // Returns a new SimulationResult keeping the samples selected by `strategy`:
+        // - "uniform": every n-th sample (see `subsample`)
+        // - "log": about `target_samples` samples logarithmically spaced in time (if there are more)
+        // - "turning_points": the first and last samples and the local extrema of the displacement
+        let n_samples = self.times.len();
+        let indices: Vec<usize> = match strategy {
+            "uniform" => return Ok(self.subsample(threshold_samples, target_samples)),
+            "log" => {
+                if n_samples <= target_samples || target_samples < 2 {
+                    return Ok(self);
+                }
+                let log_last = ((n_samples - 1) as f64).ln();
+                let mut indices: Vec<usize> = std::iter::once(0)
+                    .chain((0..target_samples).map(|k| {
+                        (log_last * k as f64 / (target_samples - 1) as f64).exp().round() as usize
+                    }))
+                    .map(|i| i.min(n_samples - 1))
+                    .collect();
+                indices.dedup();
+                indices
+            }
+            "turning_points" => {
+                let d = &self.displacements;
+                std::iter::once(0)
+                    .chain((1..n_samples.saturating_sub(1)).filter(|&i| {
+                        (d[i] >= d[i - 1] && d[i] > d[i + 1]) || (d[i] <= d[i - 1] && d[i] < d[i + 1])
+                    }))
+                    .chain((n_samples > 1).then_some(n_samples - 1))
+                    .collect()
+            }
+            _ => return Err(SimError::UnknownOption { option: "sampling strategy", value: strategy.to_string() }),
+        };
+        Ok(self.select(&indices))
+    }
+
+    pub(crate) fn finish(self, params: &SimulationParameters, well_depth: Option<f64>) -> Result<SimulationResult, SimError> {
+        // Returns the result to hand out. Without a preset: sampled with the sampling strategy of `params`,
+        // then with the energies smoothed if a smoothing window is set, and the displacements
+        // measured from the displacement reference. The preset replaces these settings:
+        // - "raw": every integration step in atomic units, energies from the potential minimum and
+        //   displacements from equilibrium (the microcanonical data as integrated, no smoothing)
+        // - "display": uniformly downsampled (smoothed if a window is set), displacements from equilibrium
+        //   (distances alongside), energies from the dissociation limit `well_depth` (bound states
+        //   negative; from the minimum for the harmonic model), all in SI units
+        match params.preset().as_deref() {
+            None => {
+                let sampled = self.sample(&params.sampling(), 8000, 2000)?;
+                let smoothed = match params.smoothing_window() {
+                    Some(window) => sampled.smooth_energies(window)?,
+                    None => sampled,
+                };
+                smoothed.with_displacement_reference(&params.displacement_reference())
+            }
+            Some("raw") => Ok(self),
+            Some("display") => {
+                let sampled = self.subsample(8000, 2000);
+                let smoothed = match params.smoothing_window() {
+                    Some(window) => sampled.smooth_energies(window)?,
+                    None => sampled,
+                };
+                let mut result = match well_depth {
+                    Some(well_depth) => smoothed.with_energy_offset(well_depth),
+                    None => smoothed,
+                };
+                if well_depth.is_none() {
+                    result.warnings.push(String::from(
+                        "No finite dissociation limit is known for the model, so the energies are not shifted"
+                    ));
+                }
+                Ok(result.into_si_units())
+            }
+            Some(preset) => Err(SimError::UnknownOption { option: "preset", value: preset.to_string() }),
+        }
+    }
+
+    fn with_energy_offset(self, offset: f64) -> SimulationResult {
+        // Returns a new SimulationResult with `offset` subtracted from the potential and total energies
+        let shift = |series: Vec<f64>| series.iter().map(|e| e - offset).collect::<Vec<f64>>();
+        SimulationResult {
+            potential_energies: shift(self.potential_energies),
+            total_energies: shift(self.total_energies),
+            raw_energies: self.raw_energies.map(|raw| EnergySeries {
+                potential_energies: shift(raw.potential_energies),
+                total_energies: shift(raw.total_energies),
+                ..raw
+            }),
+            energy_offset: self.energy_offset + offset,
+            ..self
+        }
+    }
+
+    fn into_si_units(self) -> SimulationResult {
+        // Returns a new SimulationResult with the series converted from atomic to SI units
+        // (times in s, lengths in m, velocities in m/s, energies and the virial in J)
+        if self.si_units {
+            return self;
+        }
+        let (length, energy) = (A0_TO_M as f64, HARTREE_TO_J as f64);
+        let velocity = length / AU_TIME_TO_S;
+        let scale = |factor: f64| move |series: Vec<f64>| series.iter().map(|x| x * factor).collect::<Vec<f64>>();
+        SimulationResult {
+            times: scale(AU_TIME_TO_S)(self.times),
+            displacements: scale(length)(self.displacements),
+            distances: scale(length)(self.distances),
+            velocities: scale(velocity)(self.velocities),
+            potential_energies: scale(energy)(self.potential_energies),
+            kinetic_energies: scale(energy)(self.kinetic_energies),
+            total_energies: scale(energy)(self.total_energies),
+            virial: scale(energy)(self.virial),
+            rms_velocity: self.rms_velocity * velocity,
+            max_velocity: self.max_velocity * velocity,
+            mean_virial: self.mean_virial * energy,
+            equipartition_energy: self.equipartition_energy * energy,
+            raw_energies: self.raw_energies.map(|raw| raw.map(scale(energy))),
+            displacement_offset: self.displacement_offset * length,
+            initial_amplitude: self.initial_amplitude * length,
+            energy_offset: self.energy_offset * energy,
+            si_units: true,
+            ..self
+        }
+    }
+
+    pub fn with_displacement_reference(self, reference: &str) -> Result<SimulationResult, SimError> {
+        // Returns a new SimulationResult with the displacements measured from `reference`:
+        // "equilibrium" (unchanged) or "initial" (shifted so the first sample is zero).
+        // The shift is kept in `displacement_offset`; the distances are not changed
+        let offset = match reference {
+            "equilibrium" => 0.0,
+            "initial" => self.displacements.first().copied().unwrap_or(0.0),
+            _ => return Err(SimError::UnknownOption { option: "displacement reference", value: reference.to_string() }),
+        };
+        Ok(SimulationResult {
+            displacements: self.displacements.iter().map(|x| x - offset).collect(),
+            displacement_offset: self.displacement_offset + offset,
+            ..self
+        })
+    }
+
+    pub fn smooth_energies(self, window: usize) -> Result<SimulationResult, SimError> {
+        // Returns a new SimulationResult with a centered moving average over `window` samples
+        // applied to the energy series (kept unsmoothed in `raw_energies`)
+        if window == 0 {
+            return Err(SimError::InvalidInput(String::from("Smoothing window must be at least 1 sample")));
+        }
+        let raw = EnergySeries {
+            potential_energies: self.potential_energies,
+            kinetic_energies: self.kinetic_energies,
+            total_energies: self.total_energies,
+        };
+        let smoothed = raw.clone().map(|series| moving_average(&series, window));
+        Ok(SimulationResult {
+            potential_energies: smoothed.potential_energies,
+            kinetic_energies: smoothed.kinetic_energies,
+            total_energies: smoothed.total_energies,
+            raw_energies: Some(raw),
+            ..self
+        })
+    }
+
+    fn select(self, indices: &[usize]) -> SimulationResult {
+        // Returns a new SimulationResult keeping only the samples at `indices` (in increasing order)
+        let pick = |series: Vec<f64>| indices.iter().map(|&i| series[i]).collect::<Vec<f64>>();
+        SimulationResult {
+            times: pick(self.times),
+            displacements: pick(self.displacements),
+            distances: pick(self.distances),
+            velocities: pick(self.velocities),
+            potential_energies: pick(self.potential_energies),
+            kinetic_energies: pick(self.kinetic_energies),
+            total_energies: pick(self.total_energies),
+            virial: pick(self.virial),
+            raw_energies: self.raw_energies.map(|raw| raw.map(pick)),
+            ..self
+        }
+    }
+
     pub fn subsample(self, threshold_samples: usize, target_samples: usize) -> SimulationResult {
         // Returns a new SimulationResult with data subsampled if the number of samples exceeds `threshold_samples`.
         // # Arguments
@@ -252,274 +1102,2676 @@ impl SimulationResult {
             times: self.times.into_iter().step_by(step).collect(),
             displacements: self.displacements.into_iter().step_by(step).collect(),
             distances: self.distances.into_iter().step_by(step).collect(),
+            velocities: self.velocities.into_iter().step_by(step).collect(),
             potential_energies: self.potential_energies.into_iter().step_by(step).collect(),
             kinetic_energies: self.kinetic_energies.into_iter().step_by(step).collect(),
             total_energies: self.total_energies.into_iter().step_by(step).collect(),
+            virial: self.virial.into_iter().step_by(step).collect(),
+            raw_energies: self.raw_energies.map(|raw| raw.map(|series| series.into_iter().step_by(step).collect())),
+            ..self
         }
     }
-}
 
-// Function to generate synthetic simulation data
-pub fn simulate_molecule(params: &SimulationParameters) -> Result<SimulationResult, JsValue> {
-    // Get properties for the selected element (propagate error if not found)
-    let properties = get_element_properties(&params.element())?;
-    
-    // Get the model and run the appropriate simulation
-    let model = params.model();
-    
-    let sim_result = match model.as_str() {
-        "harmonic" => {
-            let initial_sim_state = SimulationState::init_harmonic_oscillator(properties, params.temperature());
-            simulate_harmonic_oscillator(initial_sim_state, params)
-        },
-        "morse" => {
-            let initial_sim_state = SimulationState::init_morse_potential(properties, params.temperature());
-            simulate_morse_potential(initial_sim_state, params)
-        },
-        "lennard-jones" => {
-            let initial_sim_state = SimulationState::init_lennard_jones(properties, params.temperature());
-            simulate_lennard_jones(initial_sim_state, params)
-        },
-        _ => return Err(JsValue::from_str(&format!("Unsupported model: {}", model))),
-    };
-    
-    // Return the (subsampled) simulation result
-    Ok(sim_result.subsample(8000, 2000))
-}
+    pub fn energy_drift(&self) -> f64 {
+        // Returns the maximum deviation of the total energy from its initial value,
+        // relative to the initial value (absolute if the initial energy is zero).
+        let e0 = match self.total_energies.first() {
+            Some(&e0) => e0,
+            None => return 0.0,
+        };
+        let max_deviation = self.total_energies.iter().fold(0.0, |a, &e| f64::max(a, (e - e0).abs()));
+        if e0 != 0.0 { max_deviation / e0.abs() } else { max_deviation }
+    }
 
-// Function to simulate the harmonic oscillator model
-fn simulate_harmonic_oscillator(mut state: SimulationState, params: &SimulationParameters) -> SimulationResult {
-    // Initialize vectors to store simulation data
-    let mut times = Vec::new();
-    let mut displacements = Vec::new();
-    let mut distances = Vec::new();
-    let mut potential_energies = Vec::new();
-    let mut kinetic_energies = Vec::new();
-    let mut total_energies = Vec::new();
-    
-    // Get element properties
-    let properties = get_element_properties(&params.element())
-        .expect("Element not supported");
-    
-    // Calculate number of steps
-    let duration = params.duration() as f32;
-    let dt = params.timestep() as f32;
-    let steps = (duration / dt) as usize;
-    
-    // Store initial state
-    times.push(state.time as f64);
-    displacements.push(state.displacement as f64);
-    distances.push(state.displacement as f64);
-    potential_energies.push(state.potential_e as f64);
-    kinetic_energies.push(state.kinetic_e as f64);
-    total_energies.push(state.total_e as f64);
-    
-    // Time integration loop (Velocity Verlet algorithm)
-    for _ in 0..steps {
-        // Update position using current velocity and acceleration
-        let r_half = state.displacement + state.velocity * dt * 0.5;
-        
-        // Calculate new force and acceleration at half-step position
-        let force = -properties.k_au * r_half;
-        let accel = force / properties.m_au;
-        
-        // Update velocity and position
-        state.velocity += accel * dt;
-        state.displacement = r_half + state.velocity * dt * 0.5;
+    pub fn autocorrelation(&self) -> Vec<f64> {
+        // Returns the normalized displacement autocorrelation C(lag) / C(0) (mean removed)
+        // for lags of 0 to n/2 samples (assumes uniformly spaced samples).
+        let n = self.displacements.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let mean = self.displacements.iter().sum::<f64>() / n as f64;
+        let x: Vec<f64> = self.displacements.iter().map(|d| d - mean).collect();
+        let correlation = |lag: usize| {
+            x.iter().zip(&x[lag..]).map(|(a, b)| a * b).sum::<f64>() / (n - lag) as f64
+        };
+        let c0 = correlation(0);
+        if c0 == 0.0 {
+            return vec![1.0; n / 2 + 1];
+        }
+        (0..=n / 2).map(|lag| correlation(lag) / c0).collect()
+    }
+
+    pub fn turning_point_indices(&self) -> Vec<usize> {
+        // Returns the indices of the samples where the velocity changes sign (or is exactly zero),
+        // taking the sample of each sign change with the smaller speed
+        let v = &self.velocities;
+        (0..v.len())
+            .filter_map(|i| {
+                if v[i] == 0.0 {
+                    Some(i)
+                } else if i > 0 && v[i - 1] * v[i] < 0.0 {
+                    Some(if v[i - 1].abs() < v[i].abs() { i - 1 } else { i })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn oscillation_count(&self) -> u32 {
+        // Returns the number of full oscillations: two velocity sign changes per cycle
+        // (a run starting at rest counts its first sample as a turning point)
+        (self.turning_point_indices().len() / 2) as u32
+    }
+
+    pub fn relaxation_time(&self) -> Option<f64> {
+        // Returns the time at which the amplitude envelope (|displacement| at the turning points)
+        // first falls below 1/e of its initial value, interpolated between the two turning points
+        // around the crossing (None if it never does, e.g. without a thermostat cooling the run)
+        let turning_points = self.turning_point_indices();
+        let envelope = |i: usize| self.displacements[i].abs();
+        let initial = envelope(*turning_points.first()?);
+        let threshold = initial / std::f64::consts::E;
+        turning_points.windows(2).find(|pair| envelope(pair[1]) < threshold).map(|pair| {
+            let (a0, a1) = (envelope(pair[0]), envelope(pair[1]));
+            let (t0, t1) = (self.times[pair[0]], self.times[pair[1]]);
+            t0 + (t1 - t0) * (a0 - threshold) / (a0 - a1)
+        })
+    }
+
+    pub fn sample_at(&self, time: f64) -> Result<SampledState, SimError> {
+        // Returns the state at `time`, linearly interpolated between the two surrounding samples
+        // (the times need not be uniformly spaced; times outside the run give the first or last sample)
+        if !time.is_finite() {
+            return Err(SimError::NonFinite("Query time"));
+        }
+        let n_samples = self.times.len();
+        if n_samples == 0 {
+            return Err(SimError::InvalidInput(String::from("Simulation result has no samples")));
+        }
         
-        // Update force and acceleration at new position
-        state.force = -properties.k_au * state.displacement;
-        state.acceleration = state.force / properties.m_au;
+        // Find the samples on either side of `time` (binary search, so any time grid works)
+        let upper = self.times.partition_point(|&t| t <= time);
+        let (lower, upper, fraction) = if upper == 0 {
+            (0, 0, 0.0)
+        } else if upper == n_samples {
+            (n_samples - 1, n_samples - 1, 0.0)
+        } else {
+            let lower = upper - 1;
+            (lower, upper, (time - self.times[lower]) / (self.times[upper] - self.times[lower]))
+        };
+        let lerp = |series: &[f64]| series[lower] + fraction * (series[upper] - series[lower]);
         
-        // Update energies
-        state.kinetic_e = 0.5 * properties.m_au * state.velocity * state.velocity;
-        state.potential_e = 0.5 * properties.k_au * state.displacement * state.displacement;
-        state.total_e = state.kinetic_e + state.potential_e;
+        Ok(SampledState {
+            time: time.clamp(self.times[0], self.times[n_samples - 1]),
+            displacement: lerp(&self.displacements),
+            velocity: lerp(&self.velocities),
+            potential_e: lerp(&self.potential_energies),
+            kinetic_e: lerp(&self.kinetic_energies),
+            total_e: lerp(&self.total_energies),
+        })
+    }
+
+    pub fn peak_displacement(&self) -> f64 {
+        // Returns the largest distance from equilibrium reached during the run (0 without samples)
+        self.displacements.iter().fold(0.0, |a, &b| f64::max(a, b.abs()))
+    }
+
+    pub fn ke_pe_ratio(&self) -> Vec<f64> {
+        // Returns the ratio KE / PE at each sample, with the potential energy measured from the minimum
+        // (adding back any energy offset). Zero at the turning points; NaN where the potential energy
+        // is zero (at the minimum), so plots leave a gap instead of dividing by zero
+        self.kinetic_energies.iter().zip(&self.potential_energies)
+            .map(|(&ke, &pe)| {
+                let pe = pe + self.energy_offset;
+                if pe > 0.0 { ke / pe } else { f64::NAN }
+            })
+            .collect()
+    }
+
+    pub fn summary(&self) -> SimulationSummary {
+        // Returns scalar statistics of the run, without the time series.
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len().max(1) as f64;
+        let min_displacement = self.displacements.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+        let max_displacement = self.displacements.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
         
-        // Update time
-        state.time += dt;
+        // Frequency from the times the displacement crosses its mean (two crossings per period)
+        let mean_displacement = mean(&self.displacements);
+        let crossings: Vec<f64> = self.displacements.windows(2)
+            .zip(self.times.windows(2))
+            .filter(|(x, _)| (x[0] - mean_displacement) * (x[1] - mean_displacement) < 0.0)
+            .map(|(x, t)| t[0] + (t[1] - t[0]) * (mean_displacement - x[0]) / (x[1] - x[0]))
+            .collect();
+        let frequency = if crossings.len() >= 3 {
+            let half_periods = (crossings.len() - 1) as f64;
+            Some(half_periods / (2.0 * (crossings[crossings.len() - 1] - crossings[0])))
+        } else {
+            None
+        };
         
-        // Store data
-        times.push(state.time as f64);
-        displacements.push(state.displacement as f64);
-        distances.push(state.displacement as f64);
-        potential_energies.push(state.potential_e as f64);
-        kinetic_energies.push(state.kinetic_e as f64);
-        total_energies.push(state.total_e as f64);
+        SimulationSummary {
+            n_samples: self.times.len(),
+            final_time: self.times.last().copied().unwrap_or(0.0),
+            amplitude: if self.displacements.is_empty() { 0.0 } else { 0.5 * (max_displacement - min_displacement) },
+            frequency,
+            mean_potential_energy: mean(&self.potential_energies),
+            mean_kinetic_energy: mean(&self.kinetic_energies),
+            mean_total_energy: mean(&self.total_energies),
+            energy_drift: self.energy_drift(),
+            rms_velocity: self.rms_velocity,
+            max_velocity: self.max_velocity,
+        }
     }
 
-    // Temporary fix to ensure distances are positive (add 1.1 * abs(min_distance) to all distances)
-    let min_distance = distances.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-    let offset = if min_distance < 0.0 { 1.1 * min_distance.abs() } else { 0.0 };
-    distances.iter_mut().for_each(|d| *d += offset);
-    
-    SimulationResult {
-        times,
-        displacements,
-        distances,
-        potential_energies,
-        kinetic_energies,
-        total_energies,
+    pub fn to_json(&self) -> Result<String, SimError> {
+        // Serializes the result to a JSON string (same field names as the JS object)
+        serde_json::to_string(self)
+            .map_err(|e| SimError::Serialization(e.to_string()))
     }
-}
 
-// Function to simulate the Morse potential model
-fn simulate_morse_potential(mut state: SimulationState, params: &SimulationParameters) -> SimulationResult {
-    // Initialize vectors to store simulation data
-    let mut times = Vec::new();
-    let mut displacements = Vec::new();
-    let mut distances = Vec::new();
+    pub fn to_json_fields(&self, fields: &[String]) -> Result<String, SimError> {
+        // Serializes only the named fields of the result to a JSON string (unknown names are an error)
+        let value = serde_json::to_value(self).map_err(|e| SimError::Serialization(e.to_string()))?;
+        let mut all_fields = match value {
+            serde_json::Value::Object(map) => map,
+            _ => return Err(SimError::Serialization(String::from("result is not an object"))),
+        };
+        let mut selected = serde_json::Map::new();
+        for field in fields {
+            let value = all_fields.remove(field)
+                .ok_or_else(|| SimError::UnknownOption { option: "result field", value: field.clone() })?;
+            selected.insert(field.clone(), value);
+        }
+        serde_json::to_string(&selected).map_err(|e| SimError::Serialization(e.to_string()))
+    }
+
+    pub fn to_columns(&self) -> Vec<f64> {
+        // Returns the time series as one flat, column-major buffer (columns in RESULT_COLUMNS order,
+        // each `n_rows` long, so column i starts at index i * n_rows)
+        [
+            self.times.as_slice(),
+            self.displacements.as_slice(),
+            self.distances.as_slice(),
+            self.velocities.as_slice(),
+            self.potential_energies.as_slice(),
+            self.kinetic_energies.as_slice(),
+            self.total_energies.as_slice(),
+            self.virial.as_slice(),
+        ].concat()
+    }
+
+    pub fn column_layout(&self) -> ColumnLayout {
+        // Describes the layout of the buffer returned by `to_columns`
+        ColumnLayout { n_rows: self.times.len(), columns: RESULT_COLUMNS }
+    }
+}
+
+// Define struct for the state interpolated at a query time (SimulationResult::sample_at)
+#[derive(Serialize)]
+pub struct SampledState {
+    pub time: f64,          // Query time (clamped to the run)
+    pub displacement: f64,  // Interpolated displacement
+    pub velocity: f64,      // Interpolated velocity
+    pub potential_e: f64,   // Interpolated potential energy
+    pub kinetic_e: f64,     // Interpolated kinetic energy
+    pub total_e: f64,       // Interpolated total energy
+}
+
+// Column order of the flat result buffer (SimulationResult::to_columns)
+pub const RESULT_COLUMNS: &[&str] = &[
+    "times", "displacements", "distances", "velocities", "potential_energies", "kinetic_energies", "total_energies", "virial",
+];
+
+// Define struct describing the flat result buffer (row count and column order)
+#[derive(Serialize)]
+pub struct ColumnLayout {
+    pub n_rows: usize,                  // Number of time points (length of each column)
+    pub columns: &'static [&'static str], // Column names in buffer order
+}
+
+// Signature shared by the force laws: (force, potential energy) at a displacement
+// (in the floating-point type `F` of the integration)
+pub(crate) type ForceLaw<F = f32> = fn(&ElementProperties, F) -> (F, F);
+
+// Force and potential energy for the harmonic oscillator model
+fn harmonic_force<F: Float>(properties: &ElementProperties, r: F) -> (F, F) {
+    let k = F::from_f32(properties.k_au);
+    let force = -k * r;
+    let potential = F::from_f32(0.5) * k * r * r;
+    (force, potential)
+}
+
+// Force and potential energy for the Morse potential model
+fn morse_force<F: Float>(properties: &ElementProperties, r: F) -> (F, F) {
+    let (d, alpha, one) = (F::from_f32(properties.d_au), F::from_f32(properties.alpha_au), F::from_f32(1.0));
+    let exp_alpha_r = (-alpha * r).exp();
+    let force = F::from_f32(-2.0) * d * alpha * exp_alpha_r * (one - exp_alpha_r);
+    let potential = d * (one - exp_alpha_r).powi(2);
+    (force, potential)
+}
+
+// Force and potential energy for the Lennard-Jones potential model, generalized to the Mie n-6 potential
+// V = eps (6 s^n - n s^6) / (n - 6) + eps with s = r* / (r + r*), so F = -dV/dr = 6n eps (s^n - s^6) / ((n - 6)(r + r*))
+// (for n = 12: V = eps (s^12 - 2 s^6 + 1) and F = 12 eps (s^12 - s^6) / (r + r*))
+fn lennard_jones_force<F: Float>(properties: &ElementProperties, r: F) -> (F, F) {
+    let (eps, rstar) = (F::from_f32(properties.eps_au), F::from_f32(properties.rstr_au));
+    let n = properties.lj_exponent as f64;
+    let rstar_over = rstar / (r + rstar);
+    let repulsion = rstar_over.powi(properties.lj_exponent as i32);
+    let force = (F::from_f64(6.0 * n / (n - 6.0)) / (r + rstar)) * 
+                eps * (repulsion - rstar_over.powi(6));
+    let potential = eps * (F::from_f64(6.0 / (n - 6.0)) * repulsion - F::from_f64(n / (n - 6.0)) * rstar_over.powi(6) + F::from_f32(1.0));
+    (force, potential)
+}
+
+// Force and potential energy for the Kratzer potential model
+// V(r) = -2D(r_e/r) + D(r_e/r)², shifted by +D so the minimum at r = r_e is zero
+fn kratzer_force<F: Float>(properties: &ElementProperties, r: F) -> (F, F) {
+    let (d, re, one) = (F::from_f32(properties.d_au), F::from_f32(properties.rstr_au), F::from_f32(1.0));
+    let re_over = re / (r + re);
+    let force = F::from_f32(-2.0) * d * (one - re_over) * re_over / (r + re);
+    let potential = d * (one - re_over).powi(2);
+    (force, potential)
+}
+
+// Helper function to get the force law of a model (in the floating-point type `F`)
+fn get_force_law<F: Float>(model: &str) -> Result<ForceLaw<F>, SimError> {
+    match model {
+        "harmonic" => Ok(harmonic_force),
+        "morse" => Ok(morse_force),
+        "lennard-jones" => Ok(lennard_jones_force),
+        "kratzer" => Ok(kratzer_force),
+        _ => Err(SimError::UnknownModel(model.to_string())),
+    }
+}
+
+// Function to check whether a model is anharmonic (every supported model except the harmonic oscillator;
+// false for unknown models)
+pub fn is_anharmonic(model: &str) -> bool {
+    model != "harmonic" && get_force_law::<f64>(model).is_ok()
+}
+
+// Helper function to set up a run: element properties, initial state and force law for the selected model
+pub(crate) fn init_model(params: &SimulationParameters) -> Result<(ElementProperties, SimulationState, ForceLaw), SimError> {
+    // Get properties for the selected element (propagate error if not found)
+    let properties = get_element_properties(&params.element())?;
+    init_model_with(params, properties)
+}
+
+// Helper function to set up a run with the given (possibly modified) element properties
+fn init_model_with(params: &SimulationParameters, properties: ElementProperties) -> Result<(ElementProperties, SimulationState, ForceLaw), SimError> {
+    // The Lennard-Jones repulsion must be steeper than the r^-6 attraction for a well to exist
+    let properties = match params.lj_exponent() {
+        Some(lj_exponent) if lj_exponent <= 6 => {
+            return Err(SimError::InvalidInput(format!(
+                "Lennard-Jones repulsive exponent must be greater than 6 (got {})", lj_exponent
+            )));
+        }
+        Some(lj_exponent) => ElementProperties { lj_exponent, ..properties },
+        None => properties,
+    };
+    let temperature = params.initial_temperature();
+    if let Some((start, end)) = params.temperature_ramp() {
+        for ramp_temperature in [start, end] {
+            if !(ramp_temperature >= 0.0 && ramp_temperature.is_finite()) {
+                return Err(SimError::InvalidTemperature(ramp_temperature));
+            }
+        }
+    }
+    
+    // Get the model and check the element has the parameters it needs
+    let model = params.model();
+    validate_model_parameters(&model, &params.element(), &properties)?;
+    
+    // Build the appropriate initial state
+    let force_law = get_force_law(&model)?;
+    let mut initial_sim_state = match (params.init_mode().as_str(), model.as_str()) {
+        ("thermal_displacement", "harmonic") => SimulationState::init_harmonic_oscillator(properties, temperature),
+        ("thermal_displacement", "morse") => SimulationState::init_morse_potential(properties, temperature),
+        ("thermal_displacement", "lennard-jones") => SimulationState::init_lennard_jones(properties, temperature)?,
+        ("thermal_displacement", _) => {
+            // The start energy must stay below the well depth, or the stretched bond has no finite
+            // displacement (the denominator 1 - sqrt(E0/D) is zero or negative)
+            let state = SimulationState::init_kratzer(properties, temperature);
+            let in_well = state.displacement.is_finite() && state.displacement > -properties.rstr_au;
+            if !(in_well && state.potential_e.is_finite() && state.potential_e < properties.d_au) {
+                return Err(SimError::InvalidInput(format!(
+                    "Kratzer initial displacement is not finite or outside the well at {} K (got {} Bohr, energy {})",
+                    temperature, state.displacement, state.potential_e
+                )));
+            }
+            state
+        }
+        ("thermal_velocity", _) => SimulationState::init_thermal_velocity(properties, temperature, &|r| force_law(&properties, r)),
+        (init_mode, _) => return Err(SimError::UnknownOption { option: "initial condition mode", value: init_mode.to_string() }),
+    };
+    
+    // Optionally replace the deterministic initial state with a random thermal one
+    if params.thermal_random() {
+        let mut rng = Rng::new(params.seed().unwrap_or_else(random_seed));
+        initial_sim_state = SimulationState::init_thermal_random(
+            properties, temperature, &|r| force_law(&properties, r), &mut rng
+        );
+    }
+    
+    Ok((properties, initial_sim_state, force_law))
+}
+
+// Helper function to get a seed when none is given (from the JavaScript random number generator)
+#[cfg(target_arch = "wasm32")]
+fn random_seed() -> u64 {
+    (js_sys::Math::random() * (1u64 << 53) as f64) as u64
+}
+
+// (native targets have no JavaScript random number generator and seed from the system clock instead,
+// with a call counter so seeds drawn within one clock tick still differ)
+#[cfg(not(target_arch = "wasm32"))]
+fn random_seed() -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_nanos() as u64);
+    nanos ^ CALLS.fetch_add(1, Ordering::Relaxed).wrapping_mul(0x9E3779B97F4A7C15)
+}
+
+// Helper function to check an element has the (non-zero) parameters a model needs
+fn validate_model_parameters(model: &str, element: &str, properties: &ElementProperties) -> Result<(), SimError> {
+    // Every model derives its initial amplitude from the harmonic force constant (k_si = 0 divides by zero)
+    if properties.k_si == 0.0 || properties.k_au == 0.0 {
+        return Err(SimError::ZeroForceConstant(element.to_string()));
+    }
+    
+    let missing = match model {
+        "morse" => properties.d_au == 0.0 || properties.d_si == 0.0
+            || properties.alpha_au == 0.0 || properties.alpha_si == 0.0,
+        "lennard-jones" => properties.rstr_au == 0.0 || properties.eps_au == 0.0,
+        "kratzer" => properties.rstr_au == 0.0 || properties.d_au == 0.0,
+        _ => false,
+    };
+    if missing {
+        return Err(SimError::ModelParamsMissing { element: element.to_string(), model: model.to_string() });
+    }
+    
+    Ok(())
+}
+
+// Function to advance the state by one timestep (Velocity Verlet algorithm)
+// `force_law` returns the (force, potential energy) at a displacement
+pub(crate) fn verlet_step<F: Float>(state: &mut SimulationState<F>, mass: F, force_law: &impl Fn(F) -> (F, F), dt: F) {
+    let half = F::from_f32(0.5);
+    
+    // Update position using current velocity and acceleration
+    state.displacement += state.velocity * dt + half * state.acceleration * dt * dt;
+    
+    // Calculate new force and acceleration at the new position
+    let (force, potential) = force_law(state.displacement);
+    let accel = force / mass;
+    
+    // Update velocity with the average of the old and new accelerations
+    state.velocity += half * (state.acceleration + accel) * dt;
+    state.force = force;
+    state.acceleration = accel;
+    
+    // Update energies
+    state.kinetic_e = half * mass * state.velocity * state.velocity;
+    state.potential_e = potential;
+    state.total_e = state.kinetic_e + state.potential_e;
+    
+    // Update time
+    state.time += dt;
+}
+
+// Function to get the initial state of the selected model, without running the simulation
+pub fn initial_state(params: &SimulationParameters) -> Result<SimulationState, SimError> {
+    let (_, state, _) = init_model(params)?;
+    Ok(state)
+}
+
+// Function to generate synthetic simulation data
+pub fn simulate_molecule(params: &SimulationParameters) -> Result<SimulationResult, SimError> {
+    let properties = get_element_properties(&params.element())?;
+    simulate_with_properties(params, properties)
+}
+
+// Function to continue a previous run from its last sample (for long runs split into parts)
+// (the duration of `params` is that of the continuation; times continue from the previous final time.
+// The last sample must be the true end of the run, so a subsampled `prev`, whose last two samples are
+// more than one timestep apart, is rejected)
+pub fn simulate_continue(prev: &SimulationResult, params: &SimulationParameters) -> Result<SimulationResult, SimError> {
+    // Get the force law for the selected model (validating the element and model)
+    let (properties, _, force_law) = init_model(params)?;
+    
+    if prev.si_units {
+        return Err(SimError::InvalidInput(String::from("Cannot continue a result in SI units (use the \"raw\" preset)")));
+    }
+    if let [.., before_last, last] = prev.times[..] {
+        if last - before_last > params.timestep() * (1.0 + 1e-6) {
+            return Err(SimError::InvalidInput(format!(
+                "Cannot continue a subsampled result (last samples {} apart, timestep {}; use the \"raw\" preset)",
+                last - before_last, params.timestep()
+            )));
+        }
+    }
+    
+    // Rebuild the final state of the previous run
+    let (time, displacement, velocity) = match (prev.times.last(), prev.displacements.last(), prev.velocities.last()) {
+        (Some(&time), Some(&displacement), Some(&velocity)) => {
+            (time as f32, (displacement + prev.displacement_offset) as f32, velocity as f32)
+        }
+        _ => return Err(SimError::InvalidInput(String::from("Previous result has no samples to continue from"))),
+    };
+    let (force, potential) = force_law(&properties, displacement);
+    let kinetic = 0.5 * properties.m_au * velocity * velocity;
+    let state = SimulationState {
+        time,
+        displacement,
+        force,
+        acceleration: force / properties.m_au,
+        velocity,
+        kinetic_e: kinetic,
+        potential_e: potential,
+        total_e: kinetic + potential,
+    };
+    
+    // Run the continuation
+    let sim_result = simulate_in_precision(state, &properties, params)?;
+    sim_result.finish(params, dissociation_limit(&params.model(), &properties))
+}
+
+// Helper function to run the simulation with the given (possibly modified) element properties
+fn simulate_with_properties(params: &SimulationParameters, properties: ElementProperties) -> Result<SimulationResult, SimError> {
+    // Get the initial state and force law for the selected model
+    let (properties, initial_sim_state, _) = init_model_with(params, properties)?;
+    let amplitude_warning = amplitude_warning(&properties, &initial_sim_state, params.initial_temperature());
+    
+    // Run the simulation
+    let mut sim_result = simulate_in_precision(initial_sim_state, &properties, params)?;
+    sim_result.warnings.extend(amplitude_warning);
+    
+    // Return the (sampled, optionally smoothed) simulation result
+    sim_result.finish(params, dissociation_limit(&params.model(), &properties))
+}
+
+// Helper function to get the dissociation limit of a model above its potential minimum
+// (the well depth, None for the harmonic oscillator which never dissociates)
+fn dissociation_limit(model: &str, properties: &ElementProperties) -> Option<f64> {
+    model_well_depth(model, properties).ok().map(f64::from).filter(|depth| depth.is_finite())
+}
+
+// Helper function to run the selected model in the floating-point type of the precision setting
+// (the initial state is set up in f32 either way, so both precisions start from the same state)
+fn simulate_in_precision(
+    state: SimulationState,
+    properties: &ElementProperties,
+    params: &SimulationParameters
+) -> Result<SimulationResult, SimError> {
+    match params.precision().as_str() {
+        "f32" => {
+            let force_law = get_force_law::<f32>(&params.model())?;
+            simulate_model(state, properties.m_au, |r| force_law(properties, r), properties.rstr_au, params)
+        }
+        "f64" => {
+            let force_law = get_force_law::<f64>(&params.model())?;
+            simulate_model(state.convert(), properties.m_au as f64, |r| force_law(properties, r), properties.rstr_au as f64, params)
+        }
+        precision => Err(SimError::UnknownOption { option: "precision", value: precision.to_string() }),
+    }
+}
+
+// Helper function to warn when the initial amplitude is too small to resolve in f32
+// (at very low temperatures it underflows to zero, or is lost when added to the bond length)
+fn amplitude_warning(properties: &ElementProperties, state: &SimulationState, temperature: f64) -> Option<String> {
+    if temperature <= 0.0 {
+        return None;
+    }
+    // Amplitude from the initial displacement or, if larger, from the initial speed (harmonic estimate)
+    let amplitude = state.displacement.abs()
+        .max(state.velocity.abs() * (properties.m_au / properties.k_au).sqrt());
+    let resolution = f32::EPSILON * properties.rstr_au.max(1.0);
+    if amplitude > resolution {
+        return None;
+    }
+    Some(format!(
+        "Initial amplitude ({:e} Bohr) at T = {:e} K is below the numerical resolution ({:e} Bohr); the trajectory is effectively flat",
+        amplitude, temperature, resolution
+    ))
+}
+
+// Function to simulate any model given its force law
+// (distances are the displacements offset by `equilibrium_distance`)
+pub(crate) fn simulate_model<F: Float>(
+    mut state: SimulationState<F>,
+    mass: F,
+    force_law: impl Fn(F) -> (F, F),
+    equilibrium_distance: F,
+    params: &SimulationParameters
+) -> Result<SimulationResult, SimError> {
+    // Calculate number of steps (checked before anything is allocated)
+    let mut warnings = Vec::new();
+    let steps = step_count(params, &mut warnings)?;
+    
+    // Each recorded step is integrated as `substeps` smaller steps
+    let substeps = checked_substeps(params)?;
+    let dt = F::from_f64(params.timestep() / substeps as f64);
+    let partial_step = final_partial_step(params, steps);
+    let total_steps = steps + usize::from(partial_step.is_some());
+
+    // Initialize vectors to store simulation data
+    let mut times = Vec::new();
+    let mut displacements = Vec::new();
+    let mut distances = Vec::new();
+    let mut velocities = Vec::new();
     let mut potential_energies = Vec::new();
     let mut kinetic_energies = Vec::new();
     let mut total_energies = Vec::new();
+    let mut virial = Vec::new();
     
-    // Get element properties
-    let properties = get_element_properties(&params.element())
-        .expect("Element not supported");
+    // Store initial state
+    let initial_amplitude = state.displacement.to_f64();
+    let start_time = state.time.to_f64();
+    times.push(start_time);
+    displacements.push(state.displacement.to_f64());
+    distances.push((equilibrium_distance + state.displacement).to_f64());
+    velocities.push(state.velocity.to_f64());
+    potential_energies.push(state.potential_e.to_f64());
+    kinetic_energies.push(state.kinetic_e.to_f64());
+    total_energies.push(state.total_e.to_f64());
+    virial.push((equilibrium_distance + state.displacement).to_f64() * state.force.to_f64());
     
-    // Calculate number of steps
-    let duration = params.duration() as f32;
-    let dt = params.timestep() as f32;
-    let steps = (duration / dt) as usize;
+    // Accumulate velocity statistics (RMS and maximum speed)
+    let mut sum_velocity_sq = state.velocity.to_f64().powi(2);
+    let mut max_velocity = state.velocity.to_f64().abs();
     
-    // Store initial state
-    times.push(state.time as f64);
-    displacements.push(state.displacement as f64);
-    distances.push(state.displacement as f64);
-    potential_energies.push(state.potential_e as f64);
-    kinetic_energies.push(state.kinetic_e as f64);
-    total_energies.push(state.total_e as f64);
-    
-    // Time integration loop (Velocity Verlet algorithm)
-    for _ in 0..steps {
-        // Update position using current velocity and acceleration
-        let r_half = state.displacement + state.velocity * dt * 0.5;
-        
-        // Calculate new force at half-step position (Morse potential)
-        let exp_alpha_r = f32::exp(-properties.alpha_au * r_half);
-        let force = -2.0 * properties.d_au * properties.alpha_au * exp_alpha_r * (1.0 - exp_alpha_r);
-        let accel = force / properties.m_au;
-        
-        // Update velocity and position
-        state.velocity += accel * dt;
-        state.displacement = r_half + state.velocity * dt * 0.5;
-        
-        // Update force and acceleration at new position
-        let exp_alpha_r = f32::exp(-properties.alpha_au * state.displacement);
-        state.force = -2.0 * properties.d_au * properties.alpha_au * exp_alpha_r * (1.0 - exp_alpha_r);
-        state.acceleration = state.force / properties.m_au;
+    // Time integration loop (timed if requested)
+    let wall = params.reflect_at().map(F::from_f64);
+    let core = params.hard_core().then_some(-equilibrium_distance);
+    let start_ms = if params.timing() { now_ms() } else { None };
+    for step in 0..total_steps {
+        let dt = match partial_step {
+            Some(remaining) if step == steps => F::from_f64(remaining / substeps as f64),
+            _ => dt,
+        };
+        advance_recorded_step(&mut state, mass, &force_law, dt, substeps, wall, core);
         
-        // Update energies
-        state.kinetic_e = 0.5 * properties.m_au * state.velocity * state.velocity;
-        let exp_alpha_r_sq = (1.0 - exp_alpha_r).powi(2);
-        state.potential_e = properties.d_au * exp_alpha_r_sq;
-        state.total_e = state.kinetic_e + state.potential_e;
-        
-        // Update time
-        state.time += dt;
+        // Thermostat: rescale the velocity to the (linearly ramped) target temperature
+        // (skipped while the potential energy alone exceeds the target, as near a turning point when
+        // cooling: stopping the molecule there would pin it at the turning point)
+        if let Some((start, end)) = params.temperature_ramp() {
+            let temperature = start + (end - start) * ((step + 1) as f64 / steps as f64).min(1.0);
+            let target_e = F::from_f32(KB * temperature as f32 / HARTREE_TO_J);
+            if state.potential_e <= target_e {
+                rescale_to_energy(&mut state, mass, target_e);
+            }
+        }
         
         // Store data
-        times.push(state.time as f64);
-        displacements.push(state.displacement as f64);
-        distances.push(state.displacement as f64);
-        potential_energies.push(state.potential_e as f64);
-        kinetic_energies.push(state.kinetic_e as f64);
-        total_energies.push(state.total_e as f64);
+        times.push(state.time.to_f64());
+        displacements.push(state.displacement.to_f64());
+        distances.push((equilibrium_distance + state.displacement).to_f64());
+        velocities.push(state.velocity.to_f64());
+        potential_energies.push(state.potential_e.to_f64());
+        kinetic_energies.push(state.kinetic_e.to_f64());
+        total_energies.push(state.total_e.to_f64());
+        virial.push((equilibrium_distance + state.displacement).to_f64() * state.force.to_f64());
+        sum_velocity_sq += state.velocity.to_f64().powi(2);
+        max_velocity = max_velocity.max(state.velocity.to_f64().abs());
+    }
+    let compute_ms = start_ms.and_then(|start| now_ms().map(|end| (end - start).max(0.0)));
+    if let (Some(_), Some(last)) = (partial_step, times.last_mut()) {
+        // (the accumulated time carries rounding, the run ends the duration after its start by construction)
+        *last = start_time + params.duration();
     }
+    let mean_virial = virial.iter().sum::<f64>() / virial.len() as f64;
 
-    // Temporary fix to ensure distances are positive (add 1.1 * abs(min_distance) to all distances)
-    let min_distance = distances.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-    let offset = if min_distance < 0.0 { 1.1 * min_distance.abs() } else { 0.0 };
-    distances.iter_mut().for_each(|d| *d += offset);
-    
-    SimulationResult {
+    Ok(SimulationResult {
         times,
         displacements,
         distances,
+        velocities,
         potential_energies,
         kinetic_energies,
         total_energies,
+        virial,
+        compute_ms,
+        rms_velocity: (sum_velocity_sq / (total_steps + 1) as f64).sqrt(),
+        max_velocity,
+        mean_virial,
+        warnings,
+        equipartition_energy: 0.5 * KB as f64 * params.initial_temperature() / HARTREE_TO_J as f64,
+        raw_energies: None,
+        displacement_offset: 0.0,
+        energy_offset: 0.0,
+        si_units: false,
+        initial_amplitude,
+        model: params.model(),
+        element: params.element(),
+        temperature: params.initial_temperature(),
+    })
+}
+
+// Function to take the centered moving average of `series` over `window` samples
+// (an even window is widened by one to stay centered; near the ends, and for windows longer
+// than the series, each sample averages the samples available within the window)
+pub(crate) fn moving_average(series: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+    let mut prefix = vec![0.0; series.len() + 1];
+    for (i, value) in series.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + value;
     }
+    (0..series.len())
+        .map(|i| {
+            let (lo, hi) = (i.saturating_sub(half), (i + half + 1).min(series.len()));
+            (prefix[hi] - prefix[lo]) / (hi - lo) as f64
+        })
+        .collect()
 }
 
-// Function to simulate the Lennard-Jones potential model
-fn simulate_lennard_jones(mut state: SimulationState, params: &SimulationParameters) -> SimulationResult {
-    // Initialize vectors to store simulation data
-    let mut times = Vec::new();
-    let mut displacements = Vec::new();
-    let mut distances = Vec::new();
-    let mut potential_energies = Vec::new();
-    let mut kinetic_energies = Vec::new();
-    let mut total_energies = Vec::new();
+// Function to calculate the number of integration steps, rejecting invalid or excessively long runs
+// (notes about any adjustment of the run are added to `warnings`)
+pub(crate) fn step_count(params: &SimulationParameters, warnings: &mut Vec<String>) -> Result<usize, SimError> {
+    let duration = params.duration();
+    let timestep = params.timestep();
+    if !(timestep > 0.0 && timestep.is_finite()) {
+        return Err(SimError::InvalidTimestep(timestep));
+    }
+    if !(duration >= 0.0 && duration.is_finite()) {
+        return Err(SimError::InvalidDuration(duration));
+    }
     
-    // Get element properties
-    let properties = get_element_properties(&params.element())
-        .expect("Element not supported");
+    // Checked as a float so a huge ratio cannot overflow (or saturate) usize
+    let steps = (duration / timestep).floor();
+    if steps > params.max_steps() {
+        if !params.cap_steps() {
+            return Err(SimError::TooManySteps { steps, max_steps: params.max_steps() });
+        }
+        let capped = params.max_steps().floor();
+        warnings.push(format!(
+            "Step count capped at {:e} (duration / timestep gives {:e}); the run ends at t = {}",
+            capped, steps, capped * timestep
+        ));
+        return Ok(capped as usize);
+    }
     
-    // Calculate number of steps
-    let duration = params.duration() as f32;
-    let dt = params.timestep() as f32;
-    let steps = (duration / dt) as usize;
+    // The run ends at the last whole step (note it when that is noticeably short of the duration,
+    // unless a final partial step makes up the difference)
+    let final_time = steps * timestep;
+    if !params.exact_duration() && duration - final_time > 1e-6 * duration.max(timestep) {
+        warnings.push(format!(
+            "Duration {} is not a whole number of timesteps; the run ends at t = {}", duration, final_time
+        ));
+    }
+    Ok(steps as usize)
+}
+
+// Helper function to get the length of the final partial step that ends the run exactly at the duration
+// (None without exact_duration, when `steps` whole steps already reach it, or for a capped run)
+fn final_partial_step(params: &SimulationParameters, steps: usize) -> Option<f64> {
+    let (duration, timestep) = (params.duration(), params.timestep());
+    let uncapped_steps = (duration / timestep).floor();
+    let remaining = duration - steps as f64 * timestep;
+    (params.exact_duration() && steps as f64 >= uncapped_steps && remaining > 0.0).then_some(remaining)
+}
+
+// Helper function to get the number of Verlet updates per recorded timestep (at least one)
+fn checked_substeps(params: &SimulationParameters) -> Result<u32, SimError> {
+    match params.substeps() {
+        0 => Err(SimError::InvalidInput(String::from("Substeps must be at least 1"))),
+        substeps => Ok(substeps),
+    }
+}
+
+// Helper function to advance the state by one recorded timestep as `substeps` Verlet updates of `dt`,
+// reflecting off the optional wall and hard core after each update
+fn advance_recorded_step<F: Float>(
+    state: &mut SimulationState<F>,
+    mass: F,
+    force_law: &impl Fn(F) -> (F, F),
+    dt: F,
+    substeps: u32,
+    wall: Option<F>,
+    core: Option<F>
+) {
+    for _ in 0..substeps {
+        let prev_total_e = state.total_e;
+        verlet_step(state, mass, force_law, dt);
+        if let Some(wall) = wall {
+            reflect_at_wall(state, wall, prev_total_e, mass, force_law);
+        }
+        if let Some(core) = core {
+            reflect_at_wall(state, core, prev_total_e, mass, force_law);
+        }
+    }
+}
+
+// Function to elastically reflect the molecule off a hard wall at displacement `wall`
+// The allowed region is the side of the wall containing the equilibrium position.
+// On crossing, the molecule is put at the wall with reversed velocity and its speed
+// set so the total energy equals the energy before the step.
+pub(crate) fn reflect_at_wall<F: Float>(
+    state: &mut SimulationState<F>,
+    wall: F,
+    prev_total_e: F,
+    mass: F,
+    force_law: &impl Fn(F) -> (F, F)
+) {
+    // Direction pointing from the wall into the allowed region
+    let zero = F::from_f32(0.0);
+    let inward = F::from_f32(if wall > zero { -1.0 } else { 1.0 });
+    if (state.displacement - wall) * inward >= zero {
+        return;
+    }
     
-    // Store initial state
-    times.push(state.time as f64);
-    displacements.push(state.displacement as f64);
-    distances.push(state.displacement as f64);
-    potential_energies.push(state.potential_e as f64);
-    kinetic_energies.push(state.kinetic_e as f64);
-    total_energies.push(state.total_e as f64);
-    
-    // Time integration loop (Velocity Verlet algorithm)
+    // Place at the wall and move back into the allowed region
+    let (force, potential) = force_law(wall);
+    let kinetic = (prev_total_e - potential).max(zero);
+    state.displacement = wall;
+    state.velocity = inward * (F::from_f32(2.0) * kinetic / mass).sqrt();
+    state.force = force;
+    state.acceleration = force / mass;
+    state.kinetic_e = kinetic;
+    state.potential_e = potential;
+    state.total_e = kinetic + potential;
+}
+
+// Function to rescale the velocity so the total energy becomes `target_e` (keeping the direction of motion)
+// (if the potential energy alone exceeds the target the molecule is stopped where it is)
+pub(crate) fn rescale_to_energy<F: Float>(state: &mut SimulationState<F>, mass: F, target_e: F) {
+    let zero = F::from_f32(0.0);
+    let kinetic = (target_e - state.potential_e).max(zero);
+    let direction = F::from_f32(if state.velocity < zero { -1.0 } else { 1.0 });
+    state.velocity = direction * (F::from_f32(2.0) * kinetic / mass).sqrt();
+    state.kinetic_e = kinetic;
+    state.total_e = kinetic + state.potential_e;
+}
+
+// Helper function to read the high-resolution clock of the global `performance` object
+// (found in windows, workers and Node.js alike, so simulating never needs the DOM; None if missing)
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> Option<f64> {
+    use wasm_bindgen::JsCast;
+    let performance = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance")).ok()?;
+    if !performance.is_object() {
+        return None;
+    }
+    Some(performance.unchecked_into::<web_sys::Performance>().now())
+}
+
+// (native targets have no JavaScript clock and read the system clock instead)
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> Option<f64> {
+    let since_epoch = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_secs_f64() * 1000.0)
+}
+
+// Function to check the time reversibility of the integration: runs the duration of `params` forward,
+// reverses the velocity and runs the same number of steps back, returning the distance (Bohr) between
+// the final and the initial displacement (only rounding for Velocity Verlet, which is time-reversible)
+pub fn time_reverse_check(params: &SimulationParameters) -> Result<f64, SimError> {
+    let (properties, state, _) = init_model(params)?;
+    let steps = step_count(params, &mut Vec::new())?;
+    let dt = params.timestep();
+    match params.precision().as_str() {
+        "f32" => {
+            let force_law = get_force_law::<f32>(&params.model())?;
+            Ok(round_trip_error(state, properties.m_au, |r| force_law(&properties, r), dt as f32, steps))
+        }
+        "f64" => {
+            let force_law = get_force_law::<f64>(&params.model())?;
+            Ok(round_trip_error(state.convert(), properties.m_au as f64, |r| force_law(&properties, r), dt, steps))
+        }
+        precision => Err(SimError::UnknownOption { option: "precision", value: precision.to_string() }),
+    }
+}
+
+// Helper function to run `steps` steps forward and, with the velocity reversed, back again
+// (returns the displacement error of the round trip)
+fn round_trip_error<F: Float>(
+    mut state: SimulationState<F>,
+    mass: F,
+    force_law: impl Fn(F) -> (F, F),
+    dt: F,
+    steps: usize
+) -> f64 {
+    let start_displacement = state.displacement;
     for _ in 0..steps {
-        // Update position using current velocity and acceleration
-        let r_half = state.displacement + state.velocity * dt * 0.5;
-        
-        // Calculate new force at half-step position (Lennard-Jones potential)
-        let rstar_over = properties.rstr_au / (r_half + properties.rstr_au);
-        let force = (12.0 / (r_half + properties.rstr_au)) * 
-                    properties.eps_au * (rstar_over.powi(12) - rstar_over.powi(6));
-        let accel = force / properties.m_au;
-        
-        // Update velocity and position
-        state.velocity += accel * dt;
-        state.displacement = r_half + state.velocity * dt * 0.5;
+        verlet_step(&mut state, mass, &force_law, dt);
+    }
+    state.velocity = -state.velocity;
+    for _ in 0..steps {
+        verlet_step(&mut state, mass, &force_law, dt);
+    }
+    (state.displacement - start_displacement).to_f64().abs()
+}
+
+// Maximum number of steps to integrate while waiting for one oscillation to complete
+const MAX_PERIOD_STEPS: usize = 10_000_000;
+
+// Function to measure the oscillation period by integrating until the motion repeats: the time between
+// the first and the third crossing of the potential minimum (displacement zero), which works from any starting
+// phase (at rest at a turning point, or at the minimum with init_mode "thermal_velocity")
+pub fn measure_period(params: &SimulationParameters) -> Result<f64, SimError> {
+    // Get the element properties and initial state for the selected model
+    let (properties, state, _) = init_model(params)?;
+    if !(params.timestep() > 0.0 && params.timestep().is_finite()) {
+        return Err(SimError::InvalidTimestep(params.timestep()));
+    }
+    
+    // A molecule at rest at the minimum (T = 0) never oscillates
+    if state.displacement == 0.0 && state.velocity == 0.0 {
+        return Err(SimError::NoConvergence(String::from(
+            "Oscillation has no period: the molecule starts at rest at the potential minimum"
+        )));
+    }
+    
+    // Integrate in the floating-point type of the precision setting (as simulate_in_precision)
+    match params.precision().as_str() {
+        "f32" => {
+            let force_law = get_force_law::<f32>(&params.model())?;
+            measure_period_in(state, properties.m_au, |r| force_law(&properties, r), properties.rstr_au, params)
+        }
+        "f64" => {
+            let force_law = get_force_law::<f64>(&params.model())?;
+            measure_period_in(state.convert(), properties.m_au as f64, |r| force_law(&properties, r), properties.rstr_au as f64, params)
+        }
+        precision => Err(SimError::UnknownOption { option: "precision", value: precision.to_string() }),
+    }
+}
+
+// Helper function to measure the period in the floating-point type `F`, stepping as simulate_model does
+// (substeps, the reflecting wall and the hard core)
+fn measure_period_in<F: Float>(
+    mut state: SimulationState<F>,
+    mass: F,
+    force_law: impl Fn(F) -> (F, F),
+    equilibrium_distance: F,
+    params: &SimulationParameters
+) -> Result<f64, SimError> {
+    let substeps = checked_substeps(params)?;
+    let dt = F::from_f64(params.timestep() / substeps as f64);
+    let wall = params.reflect_at().map(F::from_f64);
+    let core = params.hard_core().then_some(-equilibrium_distance);
+    let zero = F::from_f32(0.0);
+    
+    // Times of the crossings of the minimum (a start exactly at the minimum counts as a crossing at t = 0)
+    let mut crossings = Vec::with_capacity(3);
+    for _ in 0..MAX_PERIOD_STEPS {
+        let prev = state.clone();
+        advance_recorded_step(&mut state, mass, &force_law, dt, substeps, wall, core);
         
-        // Update force and acceleration at new position
-        let rstar_over = properties.rstr_au / (state.displacement + properties.rstr_au);
-        state.force = (12.0 / (state.displacement + properties.rstr_au)) * 
-                      properties.eps_au * (rstar_over.powi(12) - rstar_over.powi(6));
-        state.acceleration = state.force / properties.m_au;
+        let crossed = (prev.displacement <= zero && state.displacement > zero)
+            || (prev.displacement >= zero && state.displacement < zero);
+        if !crossed {
+            continue;
+        }
         
-        // Update energies
-        state.kinetic_e = 0.5 * properties.m_au * state.velocity * state.velocity;
-        state.potential_e = properties.eps_au * (rstar_over.powi(12) - 2.0 * rstar_over.powi(6) + 1.0);
-        state.total_e = state.kinetic_e + state.potential_e;
+        // Linearly interpolate the time at which the displacement crossed zero
+        let fraction = (prev.displacement / (prev.displacement - state.displacement)).to_f64();
+        crossings.push(prev.time.to_f64() + fraction * params.timestep());
+        if let [first, _, third] = crossings[..] {
+            return Ok(third - first);
+        }
+    }
+    
+    Err(SimError::NoConvergence(format!(
+        "Oscillation did not complete a full period within {} steps", MAX_PERIOD_STEPS
+    )))
+}
+
+// Function to compute the classical action ∮ p dq (atomic units, ħ = 1) over one oscillation
+// (trapezoidal rule over every step of a run lasting exactly one measured period; E / ν for the harmonic oscillator)
+pub fn classical_action(params: &SimulationParameters) -> Result<f64, SimError> {
+    // Pin the seed once so an unseeded random start is the same trajectory for the period and the run
+    let mut cycle_params = params.clone();
+    if params.thermal_random() && params.seed().is_none() {
+        cycle_params.set_seed(Some(random_seed()));
+    }
+    let period = measure_period(&cycle_params)?;
+    let mass = get_element_properties(&params.element())?.m_au as f64;
+    
+    // Run exactly one period (a final partial step closes the loop)
+    cycle_params.duration = period;
+    cycle_params.set_exact_duration(true);
+    cycle_params.set_preset(Some(String::from("raw")));
+    let result = simulate_molecule(&cycle_params)?;
+    
+    let action = result.displacements.windows(2)
+        .zip(result.velocities.windows(2))
+        .map(|(x, v)| 0.5 * mass * (v[0] + v[1]) * (x[1] - x[0]))
+        .sum::<f64>();
+    Ok(action.abs())
+}
+
+// Ratio of the temperatures of the two runs of the anharmonicity estimate (amplitude ratio of ~10)
+const ANHARMONICITY_TEMPERATURE_RATIO: f64 = 100.0;
+
+// Function to estimate the anharmonicity as the fractional drop in frequency with amplitude:
+// 1 - ω(T) / ω(T / 100), measured from the periods at the two temperatures
+// (the same timestep is used for both, so the integration error largely cancels)
+pub fn anharmonicity(params: &SimulationParameters) -> Result<f64, SimError> {
+    if params.model() == "harmonic" {
+        return Err(SimError::NotApplicable { quantity: "Anharmonicity", model: params.model() });
+    }
+    if params.temperature() <= 0.0 {
+        return Err(SimError::InvalidInput(String::from("Anharmonicity needs a positive temperature")));
+    }
+    
+    // Measure the period at the given and at a much lower temperature (smaller amplitude)
+    let period = measure_period(params)?;
+    let mut small_params = params.clone();
+    small_params.temperature = params.temperature() / ANHARMONICITY_TEMPERATURE_RATIO;
+    let small_period = measure_period(&small_params)?;
+    
+    Ok(1.0 - small_period / period)
+}
+
+// Define result struct for ensemble statistics at each time point
+#[derive(Serialize)]
+pub struct EnsembleResult {
+    pub n_runs: usize,                       // Number of trajectories averaged
+    pub times: Vec<f64>,                     // Time points shared by all runs
+    pub mean_potential_energies: Vec<f64>,   // Ensemble mean of the potential energy
+    pub std_potential_energies: Vec<f64>,    // Ensemble standard deviation of the potential energy
+    pub mean_kinetic_energies: Vec<f64>,     // Ensemble mean of the kinetic energy
+    pub std_kinetic_energies: Vec<f64>,      // Ensemble standard deviation of the kinetic energy
+    pub mean_total_energies: Vec<f64>,       // Ensemble mean of the total energy
+    pub std_total_energies: Vec<f64>,        // Ensemble standard deviation of the total energy
+}
+
+// Running sums for the ensemble mean and standard deviation of one series
+struct SeriesStats {
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+}
+
+impl SeriesStats {
+    fn new(n: usize) -> SeriesStats {
+        SeriesStats { sum: vec![0.0; n], sum_sq: vec![0.0; n] }
+    }
+    
+    fn add(&mut self, values: &[f64]) {
+        for (i, &v) in values.iter().enumerate() {
+            self.sum[i] += v;
+            self.sum_sq[i] += v * v;
+        }
+    }
+    
+    // Returns the (mean, standard deviation) series over `n` runs
+    fn finish(self, n: usize) -> (Vec<f64>, Vec<f64>) {
+        let n = n as f64;
+        let mean: Vec<f64> = self.sum.iter().map(|s| s / n).collect();
+        let std = self.sum_sq.iter().zip(&mean).map(|(sq, m)| (sq / n - m * m).max(0.0).sqrt()).collect();
+        (mean, std)
+    }
+}
+
+// Function to average the energies over many trajectories with random thermal initial conditions
+// (run i uses seed `seed + i`)
+pub fn ensemble_average(params: &SimulationParameters, n_runs: usize, seed: u64) -> Result<EnsembleResult, SimError> {
+    if n_runs == 0 {
+        return Err(SimError::InvalidInput(String::from("Ensemble needs at least one run")));
+    }
+    
+    let mut times: Vec<f64> = Vec::new();
+    let mut potential = SeriesStats::new(0);
+    let mut kinetic = SeriesStats::new(0);
+    let mut total = SeriesStats::new(0);
+    
+    for i in 0..n_runs {
+        let mut run_params = params.clone();
+        run_params.thermal_random = true;
+        run_params.seed = Some(seed.wrapping_add(i as u64));
+        let result = simulate_molecule(&run_params)?;
         
-        // Update time
-        state.time += dt;
+        // All runs must share the time grid of the first
+        if i == 0 {
+            times = result.times.clone();
+            potential = SeriesStats::new(times.len());
+            kinetic = SeriesStats::new(times.len());
+            total = SeriesStats::new(times.len());
+        } else if result.times != times {
+            return Err(SimError::InvalidInput(String::from("Ensemble runs do not share the same time grid")));
+        }
         
-        // Store data
-        times.push(state.time as f64);
-        displacements.push(state.displacement as f64);
-        distances.push(state.displacement as f64);
-        potential_energies.push(state.potential_e as f64);
-        kinetic_energies.push(state.kinetic_e as f64);
-        total_energies.push(state.total_e as f64);
+        potential.add(&result.potential_energies);
+        kinetic.add(&result.kinetic_energies);
+        total.add(&result.total_energies);
     }
-
-    // Temporary fix to ensure distances are positive (add 1.1 * abs(min_distance) to all distances)
-    let min_distance = distances.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-    let offset = if min_distance < 0.0 { 1.1 * min_distance.abs() } else { 0.0 };
-    distances.iter_mut().for_each(|d| *d += offset);
     
-    SimulationResult {
+    let (mean_potential_energies, std_potential_energies) = potential.finish(n_runs);
+    let (mean_kinetic_energies, std_kinetic_energies) = kinetic.finish(n_runs);
+    let (mean_total_energies, std_total_energies) = total.finish(n_runs);
+    
+    Ok(EnsembleResult {
+        n_runs,
         times,
-        displacements,
-        distances,
-        potential_energies,
-        kinetic_energies,
-        total_energies,
+        mean_potential_energies,
+        std_potential_energies,
+        mean_kinetic_energies,
+        std_kinetic_energies,
+        mean_total_energies,
+        std_total_energies,
+    })
+}
+
+// Define result struct for a correlation function over lag times
+#[derive(Serialize)]
+pub struct Correlation {
+    pub lag_times: Vec<f64>,  // Lag times (multiples of the sample spacing)
+    pub values: Vec<f64>,     // Normalized correlation at each lag (1 at zero lag)
+}
+
+// Function to run the simulation and compute the normalized displacement autocorrelation
+pub fn autocorrelation(params: &SimulationParameters) -> Result<Correlation, SimError> {
+    if params.sampling() != "uniform" {
+        return Err(SimError::InvalidInput(String::from("Autocorrelation needs uniformly sampled results")));
+    }
+    let result = simulate_molecule(params)?;
+    let values = result.autocorrelation();
+    let spacing = result.times.get(1).map_or(0.0, |t1| t1 - result.times[0]);
+    Ok(Correlation {
+        lag_times: (0..values.len()).map(|lag| lag as f64 * spacing).collect(),
+        values,
+    })
+}
+
+// Function to run the simulation at each timestep and return (timestep, energy drift) pairs
+pub fn timestep_convergence(params: &SimulationParameters, timesteps: &[f64]) -> Result<Vec<(f64, f64)>, SimError> {
+    timesteps.iter()
+        .map(|&timestep| {
+            let mut run_params = params.clone();
+            run_params.timestep = timestep;
+            let result = simulate_molecule(&run_params)?;
+            Ok((timestep, result.energy_drift()))
+        })
+        .collect()
+}
+
+// Function to run the simulation at each temperature and return (temperature, peak displacement) pairs
+// (e.g. for a calibration curve of amplitude against temperature)
+pub fn amplitude_vs_temperature(
+    element: &str,
+    model: &str,
+    temperatures: &[f64],
+    duration: f64,
+    timestep: f64
+) -> Result<Vec<(f64, f64)>, SimError> {
+    temperatures.iter()
+        .map(|&temperature| {
+            let params = SimulationParameters::new(model.to_string(), element.to_string(), duration, timestep, temperature);
+            let result = simulate_molecule(&params)?;
+            Ok((temperature, result.peak_displacement()))
+        })
+        .collect()
+}
+
+// Define result struct for one isotope of an isotope comparison
+#[derive(Serialize)]
+pub struct IsotopeResult {
+    pub mass_au: f64,              // Substituted (reduced) mass in atomic units
+    pub result: SimulationResult,  // Simulation with that mass
+}
+
+// Function to run the element's potential with each substituted mass (e.g. H2, D2, T2)
+// (the potential is that of the element's first suitable model, see ElementInfo)
+pub fn isotope_comparison(
+    element: &str,
+    masses_au: &[f64],
+    duration: f64,
+    timestep: f64,
+    temperature: f64
+) -> Result<Vec<IsotopeResult>, SimError> {
+    let properties = get_element_properties(element)?;
+    let model = get_element_info(element)?.valid_models[0];
+    let params = SimulationParameters::new(model.to_string(), element.to_string(), duration, timestep, temperature);
+    
+    masses_au.iter()
+        .map(|&mass_au| {
+            if !(mass_au > 0.0 && mass_au.is_finite()) {
+                return Err(SimError::InvalidInput(format!("Mass must be positive (got {})", mass_au)));
+            }
+            let isotope = ElementProperties { m_au: mass_au as f32, ..properties };
+            Ok(IsotopeResult { mass_au, result: simulate_with_properties(&params, isotope)? })
+        })
+        .collect()
+}
+
+// Define struct for Morse parameters converted from spectroscopic constants
+#[derive(Serialize)]
+pub struct MorseParameters {
+    pub mass_au: f64,   // (Reduced) mass the constants refer to (atomic units)
+    pub d_au: f64,      // Dissociation energy D_e (atomic units)
+    pub alpha_au: f64,  // Range parameter α (atomic units)
+    pub k_au: f64,      // Force constant 2 D_e α² at the minimum (atomic units)
+}
+
+// Function to convert the spectroscopic constants ω_e and ω_e x_e (cm⁻¹) of a diatomic with (reduced)
+// mass `mass_au` to Morse parameters: D_e = ω_e² / (4 ω_e x_e) and α = ω_e sqrt(μ / (2 D_e)) (ħ = 1)
+// (physical only for 0 < ω_e x_e < ω_e / 2, i.e. at least one bound vibrational level)
+pub fn morse_from_spectroscopic(omega_e: f64, omega_e_x_e: f64, mass_au: f64) -> Result<MorseParameters, SimError> {
+    if !(omega_e > 0.0 && omega_e.is_finite()) {
+        return Err(SimError::InvalidInput(format!("ω_e must be positive (got {})", omega_e)));
+    }
+    if !(omega_e_x_e > 0.0 && omega_e_x_e < 0.5 * omega_e) {
+        return Err(SimError::InvalidInput(format!(
+            "ω_e x_e must be positive and below ω_e / 2 (got {} for ω_e = {})", omega_e_x_e, omega_e
+        )));
+    }
+    if !(mass_au > 0.0 && mass_au.is_finite()) {
+        return Err(SimError::InvalidInput(format!("Mass must be positive (got {})", mass_au)));
+    }
+    
+    // Convert to Hartree and apply the Morse relations
+    let omega_au = omega_e * HARTREE_PER_WAVENUMBER;
+    let d_au = omega_e * omega_e / (4.0 * omega_e_x_e) * HARTREE_PER_WAVENUMBER;
+    let alpha_au = omega_au * (mass_au / (2.0 * d_au)).sqrt();
+    Ok(MorseParameters { mass_au, d_au, alpha_au, k_au: 2.0 * d_au * alpha_au * alpha_au })
+}
+
+// Function to run the Morse model of the element with D_e and α from spectroscopic constants (cm⁻¹)
+// (the element provides the mass and equilibrium distance, so e.g. tabulated constants can be tried on Ar)
+pub fn simulate_morse_spectroscopic(params: &SimulationParameters, omega_e: f64, omega_e_x_e: f64) -> Result<SimulationResult, SimError> {
+    if params.model() != "morse" {
+        return Err(SimError::NotApplicable { quantity: "Spectroscopic constants", model: params.model() });
     }
+    let properties = get_element_properties(&params.element())?;
+    let morse = morse_from_spectroscopic(omega_e, omega_e_x_e, properties.m_au as f64)?;
+    
+    // Replace the Morse parameters (and the force constant they imply) in both unit systems
+    let a0 = A0_TO_M as f64;
+    let converted = ElementProperties {
+        k_au: morse.k_au as f32,
+        k_si: (morse.k_au * HARTREE_TO_J as f64 / (a0 * a0)) as f32,
+        d_au: morse.d_au as f32,
+        d_si: (morse.d_au * HARTREE_TO_J as f64) as f32,
+        alpha_au: morse.alpha_au as f32,
+        alpha_si: (morse.alpha_au / a0) as f32,
+        ..properties
+    };
+    simulate_with_properties(params, converted)
 }
 
+// Define struct for Lennard-Jones parameters interpolated to a mass
+#[derive(Serialize)]
+pub struct LennardJonesParameters {
+    pub mass_au: f64,  // Target mass (atomic units)
+    pub eps_au: f64,   // Interpolated well depth (atomic units)
+    pub rstr_au: f64,  // Interpolated equilibrium separation (atomic units)
+}
 
+// Function to interpolate the Lennard-Jones parameters to an arbitrary mass
+// (illustrative only: eps and r* are interpolated linearly in log(mass) between the two bracketing
+// elements with Lennard-Jones parameters, and clamped to the lightest or heaviest one outside their range;
+// real well depths and separations do not follow the mass)
+pub fn interpolate_lennard_jones(mass_au: f64) -> Result<LennardJonesParameters, SimError> {
+    if !(mass_au > 0.0 && mass_au.is_finite()) {
+        return Err(SimError::InvalidInput(format!("Mass must be positive (got {})", mass_au)));
+    }
+    
+    // Elements with Lennard-Jones parameters, lightest first
+    let mut known: Vec<ElementProperties> = ELEMENT_PROPERTIES.iter()
+        .filter(|(_, props)| props.eps_au != 0.0 && props.rstr_au != 0.0)
+        .map(|(_, props)| *props)
+        .collect();
+    known.sort_by(|a, b| a.m_au.total_cmp(&b.m_au));
+    let (lightest, heaviest) = match (known.first(), known.last()) {
+        (Some(&lightest), Some(&heaviest)) => (lightest, heaviest),
+        _ => return Err(SimError::InvalidInput(String::from("No element has Lennard-Jones parameters"))),
+    };
+    
+    // Find the bracketing elements and the position of the mass between them (in log(mass))
+    let (lower, upper, fraction) = if mass_au <= lightest.m_au as f64 {
+        (lightest, lightest, 0.0)
+    } else if mass_au >= heaviest.m_au as f64 {
+        (heaviest, heaviest, 0.0)
+    } else {
+        let i = known.iter().position(|props| props.m_au as f64 > mass_au).unwrap();
+        let (lower, upper) = (known[i - 1], known[i]);
+        let fraction = (mass_au / lower.m_au as f64).ln() / (upper.m_au as f64 / lower.m_au as f64).ln();
+        (lower, upper, fraction)
+    };
+    let log_interpolate = |a: f32, b: f32| (a as f64) * ((b as f64) / (a as f64)).powf(fraction);
+    
+    Ok(LennardJonesParameters {
+        mass_au,
+        eps_au: log_interpolate(lower.eps_au, upper.eps_au),
+        rstr_au: log_interpolate(lower.rstr_au, upper.rstr_au),
+    })
+}
+
+// Number of harmonic periods covered (and steps per period used) by the model comparison
+const COMPARISON_PERIODS: f32 = 5.0;
+const COMPARISON_STEPS_PER_PERIOD: f32 = 200.0;
+
+// Helper function to get the period of small oscillations (harmonic approximation, atomic units)
+fn harmonic_period(properties: &ElementProperties) -> f32 {
+    2.0 * std::f32::consts::PI * (properties.m_au / properties.k_au).sqrt()
+}
+
+// Helper function to get the curvature V''(0) of a model's potential at its minimum (atomic units)
+// (the harmonic force constant of small oscillations about the minimum)
+fn model_curvature(model: &str, properties: &ElementProperties) -> Result<f32, SimError> {
+    match model {
+        "harmonic" => Ok(properties.k_au),
+        "morse" => Ok(2.0 * properties.d_au * properties.alpha_au * properties.alpha_au),
+        "lennard-jones" => Ok(6.0 * properties.lj_exponent as f32 * properties.eps_au / (properties.rstr_au * properties.rstr_au)),
+        "kratzer" => Ok(2.0 * properties.d_au / (properties.rstr_au * properties.rstr_au)),
+        _ => Err(SimError::UnknownModel(model.to_string())),
+    }
+}
+
+// Function to get the timestep resolving the small-oscillation period of a model with `steps_per_period` steps
+pub fn timestep_for_resolution(element: &str, model: &str, steps_per_period: f64) -> Result<f64, SimError> {
+    if !(steps_per_period > 0.0 && steps_per_period.is_finite()) {
+        return Err(SimError::InvalidInput(format!("Steps per period must be positive (got {})", steps_per_period)));
+    }
+    let properties = get_element_properties(element)?;
+    validate_model_parameters(model, element, &properties)?;
+    
+    // Period of small oscillations, from the curvature of the model's potential at its minimum
+    let curvature = model_curvature(model, &properties)?;
+    let period = harmonic_period(&ElementProperties { k_au: curvature, ..properties });
+    Ok(period as f64 / steps_per_period)
+}
+
+// Steps per period of the fastest local oscillation used for the stability limit of the timestep,
+// and the points along the trajectory where the curvature of the potential is sampled
+const STABLE_STEPS_PER_PERIOD: f64 = 20.0;
+const CURVATURE_SAMPLES: usize = 200;
+
+// Function to estimate the largest timestep that integrates a run stably and accurately: a fraction
+// (1 / STABLE_STEPS_PER_PERIOD) of the period 2π sqrt(m / V'') at the largest curvature V'' reached
+// between the turning points of the initial energy. This is the small-oscillation period for the
+// harmonic model; for Lennard-Jones the steep repulsive wall dominates and gives a shorter timestep
+pub fn max_stable_timestep(element: &str, model: &str, temperature: f64) -> Result<f64, SimError> {
+    let params = SimulationParameters::new(model.to_string(), element.to_string(), 0.0, 1.0, temperature);
+    let properties = get_element_properties(element)?;
+    validate_model_parameters(model, element, &properties)?;
+    let force_law = get_force_law::<f64>(model)?;
+    let turning = turning_points(&params)?;
+    let (left, right) = (turning[0], turning[1]);
+    
+    // Curvature V'' = -dF/dr from central differences at points spanning the trajectory (and the minimum)
+    let h = 1e-4 * (right - left).max(1e-6);
+    let curvature = |r: f64| (force_law(&properties, r - h).0 - force_law(&properties, r + h).0) / (2.0 * h);
+    let max_curvature = (0..=CURVATURE_SAMPLES)
+        .map(|i| left + (right - left) * i as f64 / CURVATURE_SAMPLES as f64)
+        .chain(std::iter::once(0.0))
+        .map(curvature)
+        .fold(0.0, f64::max);
+    if !(max_curvature > 0.0 && max_curvature.is_finite()) {
+        return Err(SimError::InvalidInput(format!("Cannot estimate the curvature of the {} potential", model)));
+    }
+    let fastest_period = 2.0 * std::f64::consts::PI * (properties.m_au as f64 / max_curvature).sqrt();
+    Ok(fastest_period / STABLE_STEPS_PER_PERIOD)
+}
+
+// Labelled run of a comparison: (label, its parameters, its result)
+pub type ComparisonRun = (&'static str, SimulationParameters, SimulationResult);
+
+// Function to run the harmonic and Morse models for the same element and temperature
+// (over a few harmonic periods, so the anharmonic period shift is visible)
+pub fn model_comparison(element: &str, temperature: f64) -> Result<Vec<ComparisonRun>, SimError> {
+    // Both models must be available for the element
+    let properties = get_element_properties(element)?;
+    for model in ["harmonic", "morse"] {
+        validate_model_parameters(model, element, &properties)?;
+    }
+
+    // Choose the duration and timestep from the harmonic period
+    let period = harmonic_period(&properties) as f64;
+    let duration = COMPARISON_PERIODS as f64 * period;
+    let timestep = period / COMPARISON_STEPS_PER_PERIOD as f64;
+
+    [("Harmonic", "harmonic"), ("Morse", "morse")].into_iter()
+        .map(|(label, model)| {
+            let params = SimulationParameters::new(model.to_string(), element.to_string(), duration, timestep, temperature);
+            let result = simulate_molecule(&params)?;
+            Ok((label, params, result))
+        })
+        .collect()
+}
+
+// Helper function to get the well depth (dissociation energy) of a model, infinite for the harmonic oscillator
+fn model_well_depth(model: &str, properties: &ElementProperties) -> Result<f32, SimError> {
+    match model {
+        "harmonic" => Ok(f32::INFINITY),
+        "morse" | "kratzer" => Ok(properties.d_au),
+        "lennard-jones" => Ok(properties.eps_au),
+        _ => Err(SimError::UnknownModel(model.to_string())),
+    }
+}
+
+// Function to get the potential well depth (atomic units) of the selected model and element
+pub fn well_depth(params: &SimulationParameters) -> Result<f64, SimError> {
+    let properties = get_element_properties(&params.element())?;
+    validate_model_parameters(&params.model(), &params.element(), &properties)?;
+    Ok(model_well_depth(&params.model(), &properties)? as f64)
+}
+
+// Function to check whether the initial state is bound (total energy below the well depth)
+// without running the simulation (the harmonic oscillator is always bound, so it is an error)
+pub fn is_bound(params: &SimulationParameters) -> Result<bool, SimError> {
+    if params.model() == "harmonic" {
+        return Err(SimError::NotApplicable { quantity: "Bound/unbound classification", model: params.model() });
+    }
+    let (properties, state, _) = init_model(params)?;
+    let well_depth = model_well_depth(&params.model(), &properties)?;
+    // (a start energy above the well depth has no turning point, so its energy is NaN and unbound)
+    Ok(state.total_e < well_depth)
+}
+
+// Function to estimate the fraction of molecules dissociated at a temperature by the Boltzmann factor
+// exp(-D / k_B T) of the Morse well depth (an illustrative estimate, ignoring the degeneracy of the states)
+pub fn dissociation_fraction(element: &str, temperature: f64) -> Result<f64, SimError> {
+    if !(temperature >= 0.0 && temperature.is_finite()) {
+        return Err(SimError::InvalidTemperature(temperature));
+    }
+    let properties = get_element_properties(element)?;
+    validate_model_parameters("morse", element, &properties)?;
+    let thermal_energy = KB as f64 * temperature / HARTREE_TO_J as f64;
+    Ok((-(properties.d_au as f64) / thermal_energy).exp())
+}
+
+// Function to get the equilibrium interatomic distance (atomic units) of the selected element
+// (the equilibrium displacement is zero for all models)
+pub fn equilibrium_distance(params: &SimulationParameters) -> Result<f64, SimError> {
+    let properties = get_element_properties(&params.element())?;
+    if properties.rstr_au == 0.0 {
+        return Err(SimError::MissingEquilibriumDistance(params.element()));
+    }
+    Ok(properties.rstr_au as f64)
+}
+
+// Function to get the thermal amplitude sqrt(2 k_B T / k) (atomic units) of the harmonic model
+pub fn thermal_amplitude(params: &SimulationParameters) -> Result<f64, SimError> {
+    if params.model() != "harmonic" {
+        return Err(SimError::NotApplicable { quantity: "Thermal amplitude", model: params.model() });
+    }
+    let properties = get_element_properties(&params.element())?;
+    validate_model_parameters("harmonic", &params.element(), &properties)?;
+    let amplitude_si = (2.0 * KB as f64 * params.temperature() / properties.k_si as f64).sqrt();
+    Ok(amplitude_si / A0_TO_M as f64)
+}
+
+// Function to get the equipartition energy ½ k_B T per degree of freedom, in atomic units ("au", Hartree)
+// or SI ("si", J) (the mean kinetic energy, and for the harmonic model also the mean potential energy)
+pub fn equipartition_energy(temperature: f64, unit: &str) -> Result<f64, SimError> {
+    if !(temperature >= 0.0 && temperature.is_finite()) {
+        return Err(SimError::InvalidTemperature(temperature));
+    }
+    let energy_si = 0.5 * KB as f64 * temperature;
+    match unit {
+        "au" => Ok(energy_si / HARTREE_TO_J as f64),
+        "si" => Ok(energy_si),
+        _ => Err(SimError::UnknownOption { option: "energy unit", value: unit.to_string() }),
+    }
+}
+
+// Function to get the harmonic vibrational wavenumber (cm⁻¹), 1/(2πc) sqrt(k/μ) in SI units
+// (the harmonic and Morse models share the force constant k at the minimum)
+pub fn vibrational_wavenumber(params: &SimulationParameters) -> Result<f64, SimError> {
+    let model = params.model();
+    if model != "harmonic" && model != "morse" {
+        return Err(SimError::NotApplicable { quantity: "Vibrational wavenumber", model });
+    }
+    let properties = get_element_properties(&params.element())?;
+    validate_model_parameters(&model, &params.element(), &properties)?;
+    
+    let mass_kg = properties.m_au as f64 * ELECTRON_MASS_KG;
+    let angular_frequency = (properties.k_si as f64 / mass_kg).sqrt();
+    Ok(angular_frequency / (2.0 * std::f64::consts::PI * SPEED_OF_LIGHT_CM_PER_S))
+}
+
+// Function to sample the force of the selected model at `n_points` displacements in [r_min, r_max]
+pub fn force_curve(params: &SimulationParameters, r_min: f64, r_max: f64, n_points: usize) -> Result<Vec<(f64, f64)>, SimError> {
+    if n_points < 2 {
+        return Err(SimError::InvalidInput(String::from("Force curve needs at least 2 points")));
+    }
+    if !(r_min < r_max && r_min.is_finite() && r_max.is_finite()) {
+        return Err(SimError::InvalidInput(format!("Invalid displacement range [{}, {}]", r_min, r_max)));
+    }
+    
+    let properties = get_element_properties(&params.element())?;
+    validate_model_parameters(&params.model(), &params.element(), &properties)?;
+    let force_law = get_force_law(&params.model())?;
+    
+    Ok((0..n_points)
+        .map(|i| {
+            let r = r_min + (r_max - r_min) * i as f64 / (n_points - 1) as f64;
+            (r, force_law(&properties, r as f32).0 as f64)
+        })
+        .collect())
+}
+
+// Stretch and compression (fraction of the shortest equilibrium distance) and number of points
+// of the harmonic potential comparison
+const POTENTIAL_COMPARISON_STRETCH: f64 = 0.2;
+const POTENTIAL_COMPARISON_POINTS: usize = 201;
+
+// Function to get the residuals x_numeric(t) - x_analytic(t) of a harmonic run as (t, residual) pairs,
+// with the analytic solution x0 cos(ωt) + (v0 / ω) sin(ωt) from the same initial state
+// (every integration step in atomic units, as with the "raw" preset)
+pub fn harmonic_residuals(params: &SimulationParameters) -> Result<Vec<(f64, f64)>, SimError> {
+    if params.model() != "harmonic" {
+        return Err(SimError::NotApplicable { quantity: "Residual against the analytic solution", model: params.model() });
+    }
+    let mut run_params = params.clone();
+    run_params.set_preset(Some(String::from("raw")));
+    let (properties, _, _) = init_model(&run_params)?;
+    let result = simulate_molecule(&run_params)?;
+    
+    // (the initial state is taken from the run itself, as an unseeded random start differs between initializations)
+    let omega = (properties.k_au as f64 / properties.m_au as f64).sqrt();
+    let (x0, v0) = (result.displacements[0], result.velocities[0]);
+    Ok(result.times.iter().zip(&result.displacements)
+        .map(|(&t, &x)| (t, x - (x0 * (omega * t).cos() + v0 / omega * (omega * t).sin())))
+        .collect())
+}
+
+// Sampled potential of one element: (element, [(r, V)])
+pub type PotentialCurve = (String, Vec<(f64, f64)>);
+
+// Function to sample the harmonic potentials V(r) = ½ k r² of several elements
+// (over one shared displacement range of ±20% of the shortest of their equilibrium distances)
+pub fn harmonic_potential_curves(elements: &[String]) -> Result<Vec<PotentialCurve>, SimError> {
+    if elements.is_empty() {
+        return Err(SimError::InvalidInput(String::from("Potential comparison needs at least one element")));
+    }
+    
+    // Get the force constants and the shared displacement range
+    let mut force_constants = Vec::new();
+    let mut r_max = f64::INFINITY;
+    for element in elements {
+        let properties = get_element_properties(element)?;
+        if properties.k_au == 0.0 {
+            return Err(SimError::ZeroForceConstant(element.clone()));
+        }
+        if properties.rstr_au == 0.0 {
+            return Err(SimError::MissingEquilibriumDistance(element.clone()));
+        }
+        force_constants.push(properties.k_au as f64);
+        r_max = r_max.min(POTENTIAL_COMPARISON_STRETCH * properties.rstr_au as f64);
+    }
+    
+    Ok(elements.iter().zip(force_constants)
+        .map(|(element, k)| {
+            let curve = (0..POTENTIAL_COMPARISON_POINTS)
+                .map(|i| {
+                    let r = -r_max + 2.0 * r_max * i as f64 / (POTENTIAL_COMPARISON_POINTS - 1) as f64;
+                    (r, 0.5 * k * r * r)
+                })
+                .collect();
+            (element.clone(), curve)
+        })
+        .collect())
+}
+
+// Function to find the classical turning points (where V(r) = E_total) of the initial state
+pub fn turning_points(params: &SimulationParameters) -> Result<Vec<f64>, SimError> {
+    // Get the element properties, initial state and force law for the selected model
+    let (properties, state, force_law) = init_model(params)?;
+    let energy = state.total_e;
+    let potential = |r: f32| force_law(&properties, r).1;
+    
+    // The harmonic oscillator is solved analytically, the others are bracketed and bisected
+    let model = params.model();
+    let left_limit = match model.as_str() {
+        "harmonic" => {
+            let r = (2.0 * energy / properties.k_au).sqrt();
+            return Ok(vec![-r as f64, r as f64]);
+        },
+        "morse" => None,
+        "lennard-jones" | "kratzer" => Some(-properties.rstr_au),
+        _ => return Err(SimError::UnknownModel(model.to_string())),
+    };
+    let well_depth = model_well_depth(&model, &properties)?;
+    // (an unbound Morse start has no real initial displacement, so its energy is NaN)
+    if energy.is_nan() || energy >= well_depth {
+        return Err(SimError::InvalidInput(format!(
+            "Energy {:e} exceeds the well depth {:e} (unbound)", energy, well_depth
+        )));
+    }
+    
+    // Repulsive side: up to the wall (atoms would overlap) or expanding until V >= E
+    let mut left = match left_limit {
+        Some(limit) => limit * (1.0 - 1e-6),
+        None => -1.0,
+    };
+    while potential(left) < energy {
+        left *= 2.0;
+    }
+    
+    // Attractive side: expanding until V >= E (finite since E is below the well depth)
+    let mut right: f32 = 1.0;
+    while potential(right) < energy {
+        right *= 2.0;
+    }
+    
+    Ok(vec![
+        bisect(&potential, energy, left, 0.0) as f64,
+        bisect(&potential, energy, 0.0, right) as f64,
+    ])
+}
+
+// Helper function to find r in [a, b] with f(r) = target by bisection (f(a) and f(b) on either side of target)
+fn bisect(f: &impl Fn(f32) -> f32, target: f32, a: f32, b: f32) -> f32 {
+    let (mut lo, mut hi) = (a, b);
+    let lo_below = f(lo) < target;
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if (f(mid) < target) == lo_below { lo = mid } else { hi = mid }
+    }
+    0.5 * (lo + hi)
+}
+
+// Native unit tests of the physics (run with `cargo test`)
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    // Helper function to run a model and return its relative energy drift
+    fn drift(model: &str, element: &str, duration: f64, timestep: f64) -> f64 {
+        let params = SimulationParameters::new(model.to_string(), element.to_string(), duration, timestep, 300.0);
+        simulate_molecule(&params).unwrap().energy_drift()
+    }
+
+    // Each run covers several oscillations at about 100 steps per period or more (300 K).
+    // The tolerances are roughly ten times the drift observed when they were set, leaving room
+    // for f32 rounding while still catching a wrong force (which drifts by far more).
+
+    // Harmonic (H): period ~314 au, observed drift ~3e-5
+    #[test]
+    fn harmonic_conserves_energy() {
+        assert!(drift("harmonic", "H", 5000.0, 0.5) < 2e-4);
+    }
+
+    // Morse (H): period ~314 au, observed drift ~2e-5
+    #[test]
+    fn morse_conserves_energy() {
+        assert!(drift("morse", "H", 5000.0, 0.5) < 2e-4);
+    }
+
+    // Kratzer (H): period ~440 au, observed drift ~2e-5
+    #[test]
+    fn kratzer_conserves_energy() {
+        assert!(drift("kratzer", "H", 5000.0, 0.5) < 2e-4);
+    }
+
+    // Lennard-Jones (Hg, bound at 300 K): period ~8e4 au, observed drift ~1e-5
+    #[test]
+    fn lennard_jones_conserves_energy() {
+        assert!(drift("lennard-jones", "Hg", 200000.0, 20.0) < 1e-4);
+    }
+
+    // Lennard-Jones: the force is -dV/dr of the potential (central differences, Hg)
+    #[test]
+    fn lennard_jones_force_is_minus_the_potential_gradient() {
+        let properties = get_element_properties("Hg").unwrap();
+        let h: f32 = 1e-2;
+        for r in [-0.8, -0.4, -0.1, 0.3, 1.0, 2.5, 5.0] {
+            let (force, _) = lennard_jones_force(&properties, r);
+            let numeric = -(lennard_jones_force(&properties, r + h).1 - lennard_jones_force(&properties, r - h).1) / (2.0 * h);
+            assert!((force - numeric).abs() < 1e-2 * force.abs(), "r = {}: {} vs {}", r, force, numeric);
+        }
+    }
+
+    // Precision: at a small timestep (1e5 steps) the drift is mostly f32 rounding,
+    // which f64 removes (observed drift ~7e-6 in f32 against ~3e-7 in f64)
+    #[test]
+    fn f64_precision_drifts_less_than_f32() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 5000.0, 0.05, 300.0);
+        let drift_f32 = simulate_molecule(&params).unwrap().energy_drift();
+        params.set_precision(String::from("f64"));
+        let drift_f64 = simulate_molecule(&params).unwrap().energy_drift();
+        assert!(drift_f64 < 0.2 * drift_f32, "f64 {} vs f32 {}", drift_f64, drift_f32);
+    }
+
+    // Substeps: a coarse recorded timestep drifts less the more substeps it is split into
+    #[test]
+    fn substeps_reduce_energy_drift() {
+        let drift_with = |substeps| {
+            let mut params = SimulationParameters::new(String::from("morse"), String::from("H"), 5000.0, 5.0, 3000.0);
+            params.set_substeps(substeps);
+            let result = simulate_molecule(&params).unwrap();
+            assert_eq!(result.times.len(), 1001);
+            result.energy_drift()
+        };
+        assert!(drift_with(4) < drift_with(1));
+    }
+
+    // Initial state: the first sample of every model's run
+    #[test]
+    fn initial_state_matches_first_sample() {
+        for (model, element) in [("harmonic", "H"), ("morse", "H"), ("lennard-jones", "Hg"), ("kratzer", "H")] {
+            let params = SimulationParameters::new(model.to_string(), element.to_string(), 100.0, 0.5, 300.0);
+            let state = initial_state(&params).unwrap();
+            let result = simulate_molecule(&params).unwrap();
+            assert_eq!(state.time as f64, result.times[0]);
+            assert_eq!(state.displacement as f64, result.displacements[0]);
+            assert_eq!(state.velocity as f64, result.velocities[0]);
+            assert_eq!(state.potential_e as f64, result.potential_energies[0]);
+            assert_eq!(state.kinetic_e as f64, result.kinetic_energies[0]);
+            assert_eq!(state.total_e as f64, result.total_energies[0]);
+        }
+    }
+
+    // Unknown elements: the closest supported symbol is suggested
+    #[test]
+    fn unknown_element_suggests_closest_symbol() {
+        let suggestion = |element: &str| match get_element_properties(element) {
+            Err(SimError::UnknownElement { suggestion, .. }) => suggestion,
+            _ => panic!("{} should be unknown", element),
+        };
+        assert_eq!(suggestion("Hg2").as_deref(), Some("Hg"));
+        assert_eq!(suggestion("ar").as_deref(), Some("Ar"));
+        assert_eq!(suggestion("Xe"), None);
+    }
+
+    // Virial theorem (harmonic): the time-averaged virial is -2 * the mean kinetic energy
+    // (r_eq * mean force averages out as 1 / duration, so the run covers several hundred periods)
+    #[test]
+    fn harmonic_virial_is_twice_the_kinetic_energy() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 200000.0, 1.0, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let mean_kinetic = result.summary().mean_kinetic_energy;
+        assert!((result.mean_virial.abs() - 2.0 * mean_kinetic).abs() < 0.02 * 2.0 * mean_kinetic);
+    }
+
+    // Lennard-Jones interpolation: exact at a tabulated mass
+    #[test]
+    fn interpolate_lennard_jones_at_argon_mass_gives_argon() {
+        let argon = get_element_properties("Ar").unwrap();
+        let interpolated = interpolate_lennard_jones(argon.m_au as f64).unwrap();
+        assert_eq!(interpolated.eps_au, argon.eps_au as f64);
+        assert_eq!(interpolated.rstr_au, argon.rstr_au as f64);
+    }
+
+    // Earlier midpoint scheme (force at r + v dt / 2), kept to compare the velocity Verlet step against
+    fn midpoint_step(state: &mut SimulationState, mass: f32, force_law: &impl Fn(f32) -> (f32, f32), dt: f32) {
+        let r_half = state.displacement + state.velocity * dt * 0.5;
+        state.velocity += force_law(r_half).0 / mass * dt;
+        state.displacement = r_half + state.velocity * dt * 0.5;
+        let (force, potential) = force_law(state.displacement);
+        state.force = force;
+        state.acceleration = force / mass;
+        state.kinetic_e = 0.5 * mass * state.velocity * state.velocity;
+        state.potential_e = potential;
+        state.total_e = state.kinetic_e + potential;
+        state.time += dt;
+    }
+
+    // Velocity Verlet: both schemes are second order, so the drift stays close to the midpoint scheme's
+    // (observed: 2.8e-5 vs 4.0e-5 at dt = 0.5, 2.4e-3 vs 2.3e-3 at dt = 5)
+    #[test]
+    fn velocity_verlet_drift_matches_midpoint_scheme() {
+        type Step = fn(&mut SimulationState, f32, &dyn Fn(f32) -> (f32, f32), f32);
+        for timestep in [0.5, 5.0] {
+            let params = SimulationParameters::new(String::from("morse"), String::from("H"), 20000.0, timestep, 300.0);
+            let (properties, initial, force_law) = init_model(&params).unwrap();
+            let force = |r| force_law(&properties, r);
+            let drift = |step: Step| {
+                let mut state = initial.clone();
+                let mut max_deviation: f32 = 0.0;
+                for _ in 0..(20000.0 / timestep) as usize {
+                    step(&mut state, properties.m_au, &force, timestep as f32);
+                    max_deviation = max_deviation.max((state.total_e - initial.total_e).abs());
+                }
+                max_deviation / initial.total_e
+            };
+            let velocity_verlet = drift(|s, m, f, dt| verlet_step(s, m, &f, dt));
+            let midpoint = drift(|s, m, f, dt| midpoint_step(s, m, &f, dt));
+            assert!(velocity_verlet < 1.5 * midpoint);
+        }
+    }
+
+    // Continuation: two consecutive parts follow the same trajectory as one long run
+    #[test]
+    fn continued_run_matches_long_run() {
+        let part = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+        let long = SimulationParameters::new(String::from("morse"), String::from("H"), 1000.0, 0.5, 300.0);
+        let first = simulate_molecule(&part).unwrap();
+        let second = simulate_continue(&first, &part).unwrap();
+        let whole = simulate_molecule(&long).unwrap();
+        assert_eq!(second.times[0], first.times[1000]);
+        for (i, (t, x)) in second.times.iter().zip(&second.displacements).enumerate() {
+            assert!((t - whole.times[1000 + i]).abs() < 1e-3);
+            assert!((x - whole.displacements[1000 + i]).abs() < 1e-6);
+        }
+    }
+
+    // Thermal velocity mode: starts at the minimum with kinetic energy k_B T / 2
+    #[test]
+    fn thermal_velocity_mode_starts_at_the_minimum() {
+        for (model, element) in [("harmonic", "H"), ("morse", "H"), ("lennard-jones", "Ar"), ("kratzer", "H")] {
+            let mut params = SimulationParameters::new(model.to_string(), element.to_string(), 100.0, 0.5, 300.0);
+            params.set_init_mode(String::from("thermal_velocity"));
+            let state = initial_state(&params).unwrap();
+            let thermal_kinetic = 0.5 * KB * 300.0 / HARTREE_TO_J;
+            assert_eq!(state.displacement, 0.0);
+            assert_eq!(state.potential_e, 0.0);
+            assert!((state.kinetic_e - thermal_kinetic).abs() < 1e-6 * thermal_kinetic);
+        }
+    }
+
+    // Timestep for a resolution: steps_per_period timesteps make up the (small-amplitude) measured period
+    #[test]
+    fn timestep_for_resolution_covers_the_period() {
+        for (model, element) in [("harmonic", "H"), ("morse", "H"), ("kratzer", "H"), ("lennard-jones", "Ar")] {
+            let timestep = timestep_for_resolution(element, model, 200.0).unwrap();
+            let params = SimulationParameters::new(model.to_string(), element.to_string(), 0.0, timestep, 1.0);
+            let period = measure_period(&params).unwrap();
+            assert!((200.0 * timestep - period).abs() < 0.01 * period);
+        }
+    }
+
+    // Turning points: two per period (harmonic, about 9.6 periods), at the extremes of the displacement
+    #[test]
+    fn harmonic_turning_points_from_velocity_sign_changes() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 3000.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let indices = result.turning_point_indices();
+        let amplitude = result.displacements[0];
+        assert_eq!(indices.len(), 20);
+        for i in indices {
+            assert!((result.displacements[i].abs() - amplitude).abs() < 1e-3 * amplitude);
+        }
+    }
+
+    // Smoothing: a constant series is unchanged (also with a window longer than the data),
+    // a noisy one loses most of its variance
+    #[test]
+    fn moving_average_keeps_constants_and_reduces_noise() {
+        let constant = vec![2.5; 50];
+        for window in [1, 4, 5, 500] {
+            assert!(moving_average(&constant, window).iter().all(|&x| (x - 2.5).abs() < 1e-12));
+        }
+        let mut rng = Rng::new(7);
+        let noisy: Vec<f64> = (0..2000).map(|_| rng.normal()).collect();
+        let variance = |x: &[f64]| {
+            let mean = x.iter().sum::<f64>() / x.len() as f64;
+            x.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / x.len() as f64
+        };
+        assert!(variance(&moving_average(&noisy, 21)) < 0.2 * variance(&noisy));
+    }
+
+    // Equipartition: ½ k_B T in SI, the same in Hartree, and in the result of a run
+    #[test]
+    fn equipartition_energy_is_half_kt() {
+        let expected = 0.5 * KB as f64 * 300.0;
+        assert!((equipartition_energy(300.0, "si").unwrap() - expected).abs() < 1e-9 * expected);
+        assert!((equipartition_energy(300.0, "au").unwrap() * HARTREE_TO_J as f64 - expected).abs() < 1e-9 * expected);
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 100.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        assert_eq!(result.equipartition_energy, equipartition_energy(300.0, "au").unwrap());
+    }
+
+    // Hard core: at 5e5 K the harmonic amplitude (~2.3 Bohr) exceeds the H bond length (1.4 Bohr),
+    // so the distance goes negative unless the hard core reflects it at zero
+    #[test]
+    fn hard_core_keeps_the_distance_nonnegative() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 2000.0, 0.5, 5.0e5);
+        let min_distance = |params: &SimulationParameters| {
+            simulate_molecule(params).unwrap().distances.iter().fold(f64::INFINITY, |a, &b| a.min(b))
+        };
+        assert!(min_distance(&params) < 0.0);
+        params.set_hard_core(true);
+        assert!(min_distance(&params) >= 0.0);
+    }
+
+    // Relaxation time: cooling linearly to 0 K over D leaves E ~ (1 - t/D), so the amplitude
+    // ~ sqrt(1 - t/D) reaches 1/e at t = D (1 - e^-2); an undamped run never relaxes
+    #[test]
+    fn cooled_run_relaxes_and_undamped_run_does_not() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 6000.0, 0.5, 300.0);
+        assert_eq!(simulate_molecule(&params).unwrap().relaxation_time(), None);
+        params.set_temperature_ramp(300.0, 0.0);
+        let expected = 6000.0 * (1.0 - (-2.0f64).exp());
+        let relaxation_time = simulate_molecule(&params).unwrap().relaxation_time().unwrap();
+        assert!((relaxation_time - expected).abs() < 0.02 * expected, "{} vs {}", relaxation_time, expected);
+    }
+
+    // Spectroscopic constants: the tabulated ω_e = 4401.21 and ω_e x_e = 121.34 cm⁻¹ of H2 give the built-in Morse parameters
+    #[test]
+    fn h2_spectroscopic_constants_give_the_built_in_morse_parameters() {
+        let properties = get_element_properties("H").unwrap();
+        let morse = morse_from_spectroscopic(4401.21, 121.34, properties.m_au as f64).unwrap();
+        for (converted, built_in) in [(morse.d_au, properties.d_au), (morse.alpha_au, properties.alpha_au), (morse.k_au, properties.k_au)] {
+            assert!((converted - built_in as f64).abs() < 1e-2 * built_in as f64, "{} vs {}", converted, built_in);
+        }
+        assert!(morse_from_spectroscopic(4401.21, 2500.0, properties.m_au as f64).is_err());
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 1000.0, 0.5, 300.0);
+        assert!(simulate_morse_spectroscopic(&params, 4401.21, 121.34).unwrap().energy_drift() < 2e-4);
+    }
+
+    // Time reversal: Velocity Verlet retraces its steps up to rounding
+    // (observed error up to ~3e-6 of the amplitude in f32 over 2e4 steps, ~1e-14 in f64)
+    #[test]
+    fn verlet_round_trip_returns_to_the_start() {
+        for (model, element) in [("harmonic", "H"), ("morse", "H"), ("lennard-jones", "Ar"), ("kratzer", "H")] {
+            let mut params = SimulationParameters::new(model.to_string(), element.to_string(), 10000.0, 0.5, 300.0);
+            let amplitude = initial_state(&params).unwrap().displacement.abs() as f64;
+            assert!(time_reverse_check(&params).unwrap() < 1e-4 * amplitude, "{}", model);
+            params.set_precision(String::from("f64"));
+            assert!(time_reverse_check(&params).unwrap() < 1e-12 * amplitude, "{} (f64)", model);
+        }
+    }
+
+    // Dissociation fraction: grows with temperature, negligible at room temperature for H (D / k_B T ~ 190),
+    // and undefined for elements without Morse parameters
+    #[test]
+    fn dissociation_fraction_grows_with_temperature() {
+        let fractions: Vec<f64> = [300.0, 3000.0, 30000.0].iter().map(|&t| dissociation_fraction("H", t).unwrap()).collect();
+        assert!(fractions[0] < 1e-50);
+        assert!(fractions[0] < fractions[1] && fractions[1] < fractions[2]);
+        assert_eq!(dissociation_fraction("H", 0.0).unwrap(), 0.0);
+        assert!(dissociation_fraction("Ar", 300.0).is_err());
+    }
+
+    // Mie potential: exponent 12 is the standard Lennard-Jones potential (the same run bit for bit);
+    // a softer repulsive wall (n = 9) starts at the same energy but further compressed
+    #[test]
+    fn mie_exponent_12_is_lennard_jones() {
+        let mut params = SimulationParameters::new(String::from("lennard-jones"), String::from("Hg"), 20000.0, 10.0, 300.0);
+        let standard = simulate_molecule(&params).unwrap();
+        params.set_lj_exponent(Some(12));
+        let mie = simulate_molecule(&params).unwrap();
+        assert_eq!(mie.displacements, standard.displacements);
+        assert_eq!(mie.total_energies, standard.total_energies);
+        params.set_lj_exponent(Some(9));
+        let soft = simulate_molecule(&params).unwrap();
+        assert!((soft.total_energies[0] - standard.total_energies[0]).abs() < 1e-3 * standard.total_energies[0]);
+        assert!(soft.displacements[0] < standard.displacements[0]);
+        assert!(soft.energy_drift() < 1e-3);
+        params.set_lj_exponent(Some(6));
+        assert!(simulate_molecule(&params).is_err());
+    }
+
+    // Energy ratio: KE / PE is zero at the initial turning point and crosses 1 four times per period
+    // (KE = PE twice on each swing; about 5 periods here), with no infinite values
+    #[test]
+    fn ke_pe_ratio_oscillates() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 1600.0, 0.5, 300.0);
+        let ratio = simulate_molecule(&params).unwrap().ke_pe_ratio();
+        assert_eq!(ratio[0], 0.0);
+        assert!(ratio.iter().all(|r| r.is_finite() || r.is_nan()));
+        let crossings = ratio.windows(2).filter(|w| (w[0] < 1.0) != (w[1] < 1.0)).count();
+        assert!((18..=22).contains(&crossings), "{} crossings", crossings);
+    }
+
+    // Exact duration: a final partial step ends the run at the duration instead of the last whole step
+    // (also when continuing a run)
+    #[test]
+    fn exact_duration_final_time() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 10.3, 1.0, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        assert_eq!(*result.times.last().unwrap(), 10.0);
+        assert!(result.warnings.iter().any(|w| w.contains("not a whole number")));
+        params.set_exact_duration(true);
+        let result = simulate_molecule(&params).unwrap();
+        assert_eq!(result.times.len(), 12);
+        assert_eq!(*result.times.last().unwrap(), 10.3);
+        assert!(result.warnings.is_empty());
+
+        // A continued run ends the duration after its start (its times keep increasing)
+        let continued = simulate_continue(&result, &params).unwrap();
+        assert!((continued.times.last().unwrap() - 20.6).abs() < 1e-5, "{:?}", continued.times.last());
+        assert!(continued.times.windows(2).all(|w| w[1] > w[0]));
+        assert!(continued.times[0] > *result.times.last().unwrap() - 1e-5);
+    }
+
+    // Harmonic residuals: zero at the start, growing with the accumulated phase error of Verlet
+    // (only defined for the harmonic model)
+    #[test]
+    fn harmonic_residuals_accumulate() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 5000.0, 0.5, 300.0);
+        let residuals = harmonic_residuals(&params).unwrap();
+        let amplitude = initial_state(&params).unwrap().displacement.abs() as f64;
+        let peak = |part: &[(f64, f64)]| part.iter().fold(0.0, |a, &(_, r)| f64::max(a, r.abs()));
+        let n = residuals.len();
+        assert!(residuals[0].1.abs() < 1e-6 * amplitude);
+        assert!(peak(&residuals[n - n / 10..]) > 2.0 * peak(&residuals[..n / 10]));
+        assert!(peak(&residuals) < 0.1 * amplitude);
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 5000.0, 0.5, 300.0);
+        assert!(harmonic_residuals(&params).is_err());
+    }
+
+    // Stable timestep: Verlet keeps the energy within a few percent at the limit, not at 5 times it
+    // (observed drift 1-2.5% at the limit, 30% to > 1000x at 5 times it, over 20 fastest periods)
+    #[test]
+    fn stable_timestep_limit_conserves_energy() {
+        // (Ar is bound by Lennard-Jones only below ~140 K)
+        for (model, element, temperature) in [("harmonic", "H", 300.0), ("morse", "H", 300.0), ("lennard-jones", "Ar", 30.0), ("kratzer", "H", 300.0)] {
+            let dt = max_stable_timestep(element, model, temperature).unwrap();
+            let drift = |timestep: f64| {
+                let params = SimulationParameters::new(model.to_string(), element.to_string(), 400.0 * dt, timestep, temperature);
+                simulate_molecule(&params).unwrap().energy_drift()
+            };
+            assert!(drift(dt) < 0.03, "{}: {}", model, drift(dt));
+            assert!(drift(5.0 * dt) > 0.25, "{}: {}", model, drift(5.0 * dt));
+        }
+        assert!(max_stable_timestep("H", "lennard-jones", 300.0).is_err());
+    }
+
+    // No DOM: calling into JavaScript (e.g. window()) panics on native targets, so completing runs of
+    // every model, with timing and seeded random initial conditions, shows the simulate path never does
+    #[test]
+    fn simulation_runs_without_javascript() {
+        for (model, element) in [("harmonic", "H"), ("morse", "H"), ("lennard-jones", "Ar"), ("kratzer", "H")] {
+            let mut params = SimulationParameters::new(model.to_string(), element.to_string(), 100.0, 0.5, 300.0);
+            params.set_timing(true);
+            assert!(simulate_molecule(&params).unwrap().compute_ms.is_some());
+            params.set_thermal_random(true);
+            params.set_seed(Some(7));
+            assert!(simulate_molecule(&params).is_ok());
+        }
+    }
+
+    // Classical action: ∮ p dq = E / ν = 2π E / ω for the harmonic oscillator
+    #[test]
+    fn harmonic_action_is_energy_over_frequency() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 0.0, 0.5, 300.0);
+        let properties = get_element_properties("H").unwrap();
+        let omega = (properties.k_au as f64 / properties.m_au as f64).sqrt();
+        let energy = initial_state(&params).unwrap().total_e as f64;
+        let expected = 2.0 * std::f64::consts::PI * energy / omega;
+        let action = classical_action(&params).unwrap();
+        assert!((action - expected).abs() < 1e-4 * expected, "{} vs {}", action, expected);
+    }
+
+    // Amplitude sweep: the harmonic peak displacement grows with temperature (as sqrt(T))
+    #[test]
+    fn harmonic_amplitude_grows_with_temperature() {
+        let temperatures = [10.0, 100.0, 300.0, 1000.0, 3000.0];
+        let amplitudes = amplitude_vs_temperature("H", "harmonic", &temperatures, 500.0, 0.5).unwrap();
+        assert!(amplitudes.windows(2).all(|pair| pair[1].1 > pair[0].1));
+        let expected_ratio = (3000.0f64 / 10.0).sqrt();
+        assert!((amplitudes[4].1 / amplitudes[0].1 - expected_ratio).abs() < 1e-2 * expected_ratio);
+    }
+
+    // Initial amplitude: the r0 of the initial conditions, which start at rest at r0 for every model
+    #[test]
+    fn initial_amplitude_is_the_first_displacement() {
+        for (model, element) in [("harmonic", "H"), ("morse", "H"), ("lennard-jones", "Ar"), ("kratzer", "H")] {
+            let mut params = SimulationParameters::new(model.to_string(), element.to_string(), 100.0, 0.5, 300.0);
+            let result = simulate_molecule(&params).unwrap();
+            assert_eq!(result.velocities[0], 0.0);
+            assert_eq!(result.initial_amplitude, result.displacements[0], "{}", model);
+            params.set_displacement_reference(String::from("initial"));
+            assert_eq!(simulate_molecule(&params).unwrap().initial_amplitude, result.initial_amplitude);
+        }
+    }
+
+    // Result metadata: the run parameters are echoed in the result and serialized with it
+    #[test]
+    fn result_reports_its_parameters() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 100.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        assert_eq!(result.model, "morse");
+        assert_eq!(result.element, "H");
+        assert_eq!(result.temperature, 300.0);
+        let json = result.to_json_fields(&[String::from("model"), String::from("element"), String::from("temperature")]).unwrap();
+        assert_eq!(json, r#"{"element":"H","model":"morse","temperature":300.0}"#);
+    }
+
+    // Element pairs: only homonuclear pairs of tabulated elements have parameters
+    #[test]
+    fn only_homonuclear_pairs_are_supported() {
+        assert!(is_supported_pair("H", "H"));
+        assert!(is_supported_pair("Ar", "Ar"));
+        assert!(!is_supported_pair("H", "Ar"));
+        assert!(!is_supported_pair("Xe", "Xe"));
+    }
+
+    // Anharmonic models: all but the harmonic oscillator (unknown models are not)
+    #[test]
+    fn anharmonic_models() {
+        assert!(!is_anharmonic("harmonic"));
+        assert!(is_anharmonic("morse"));
+        assert!(is_anharmonic("lennard-jones"));
+        assert!(is_anharmonic("kratzer"));
+        assert!(!is_anharmonic("quartic"));
+    }
+
+    // Presets: "raw" keeps every step in atomic units, "display" downsamples and converts to SI units
+    // with energies from the dissociation limit (the same first sample either way)
+    #[test]
+    fn raw_and_display_presets_of_the_same_run() {
+        let mut params = SimulationParameters::new(String::from("morse"), String::from("H"), 5000.0, 0.5, 300.0);
+        params.set_preset(Some(String::from("raw")));
+        let raw = simulate_molecule(&params).unwrap();
+        params.set_preset(Some(String::from("display")));
+        let display = simulate_molecule(&params).unwrap();
+        assert_eq!(raw.times.len(), 10001);
+        assert!(display.times.len() < raw.times.len());
+        assert!(!raw.si_units && display.si_units);
+        let d_au = well_depth(&params).unwrap();
+        assert_eq!(display.energy_offset, d_au * HARTREE_TO_J as f64);
+        let close = |si: f64, au: f64, factor: f64| (si - au * factor).abs() <= 1e-9 * (au * factor).abs();
+        assert!(close(display.displacements[0], raw.displacements[0], A0_TO_M as f64));
+        assert!(close(display.total_energies[0], raw.total_energies[0] - d_au, HARTREE_TO_J as f64));
+        assert!(display.total_energies.iter().all(|&e| e < 0.0));
+        params.set_preset(Some(String::from("pretty")));
+        assert!(simulate_molecule(&params).is_err());
+    }
+
+    // Lennard-Jones initial state: 1e40 K overflows f32 (the energy was NaN) and is reported as an error
+    #[test]
+    fn overflowing_lennard_jones_temperature_is_an_error() {
+        let params = SimulationParameters::new(String::from("lennard-jones"), String::from("Ar"), 100.0, 0.5, 1e40);
+        assert!(matches!(initial_state(&params), Err(SimError::InvalidInput(_))));
+        let params = SimulationParameters::new(String::from("lennard-jones"), String::from("Ar"), 100.0, 0.5, 1e6);
+        assert!(initial_state(&params).unwrap().potential_e.is_finite());
+    }
+
+    // Displacement reference: "initial" starts the displacements at zero, keeps the distances,
+    // and still continues from the true final state
+    #[test]
+    fn initial_reference_starts_displacements_at_zero() {
+        let mut params = SimulationParameters::new(String::from("morse"), String::from("H"), 1000.0, 0.5, 300.0);
+        let from_equilibrium = simulate_molecule(&params).unwrap();
+        params.set_displacement_reference(String::from("initial"));
+        let from_initial = simulate_molecule(&params).unwrap();
+        assert_eq!(from_initial.displacements[0], 0.0);
+        assert_eq!(from_initial.displacement_offset, from_equilibrium.displacements[0]);
+        assert_eq!(from_initial.distances, from_equilibrium.distances);
+        let continued = simulate_continue(&from_initial, &params).unwrap();
+        assert_eq!(continued.distances, simulate_continue(&from_equilibrium, &params).unwrap().distances);
+        params.set_displacement_reference(String::from("final"));
+        assert!(simulate_molecule(&params).is_err());
+    }
+
+    // Oscillation count: a run of 5.2 measured periods reports 5 full cycles
+    #[test]
+    fn five_period_run_counts_five_oscillations() {
+        let period = measure_period(&SimulationParameters::new(String::from("harmonic"), String::from("H"), 0.0, 0.5, 300.0)).unwrap();
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 5.2 * period, 0.5, 300.0);
+        assert_eq!(simulate_molecule(&params).unwrap().oscillation_count(), 5);
+    }
+
+    // Temperature ramp: heating from 10 K to 1000 K makes the late oscillation wider than the early one
+    // (amplitude ~ sqrt(T); observed ratio of the last to the first fifth ~2.1)
+    #[test]
+    fn heating_ramp_grows_the_amplitude() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 6000.0, 0.5, 300.0);
+        params.set_temperature_ramp(10.0, 1000.0);
+        let result = simulate_molecule(&params).unwrap();
+        let rms = |x: &[f64]| (x.iter().map(|x| x * x).sum::<f64>() / x.len() as f64).sqrt();
+        let n = result.displacements.len();
+        let early = rms(&result.displacements[..n / 5]);
+        let late = rms(&result.displacements[n - n / 5..]);
+        assert!(late > 1.5 * early);
+    }
+
+    // Distances: offset by the equilibrium bond length, whatever the temperature
+    #[test]
+    fn distances_share_the_equilibrium_baseline() {
+        for temperature in [100.0, 1000.0] {
+            let params = SimulationParameters::new(String::from("morse"), String::from("H"), 1000.0, 0.5, temperature);
+            let result = simulate_molecule(&params).unwrap();
+            for (d, x) in result.distances.iter().zip(&result.displacements) {
+                assert!((d - x - 1.4011).abs() < 1e-5);
+            }
+        }
+    }
+
+    // JSON export: the string parses back into a result with the same series lengths
+    #[test]
+    fn json_round_trip_keeps_the_series() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let parsed: SimulationResult = serde_json::from_str(&result.to_json().unwrap()).unwrap();
+        let n = result.times.len();
+        assert_eq!(n, 1001);
+        for series in [&parsed.times, &parsed.displacements, &parsed.distances, &parsed.velocities,
+                       &parsed.potential_energies, &parsed.kinetic_energies, &parsed.total_energies] {
+            assert_eq!(series.len(), n);
+        }
+        // (serde_json parses floats to within an ulp, not always bit for bit)
+        for (a, b) in parsed.displacements.iter().zip(&result.displacements) {
+            assert!((a - b).abs() <= 1e-15 * b.abs());
+        }
+    }
+
+    // Kratzer potential: minimal (zero) with zero force at r = r_e, restoring on either side, and
+    // stable at a low temperature. H uses its equilibrium bond length r_e = 1.4011 Bohr as rstr_au
+    #[test]
+    fn kratzer_minimum_is_at_the_equilibrium_distance() {
+        let properties = get_element_properties("H").unwrap();
+        assert_eq!(properties.rstr_au, 1.4011);
+        assert_eq!(kratzer_force(&properties, 0.0_f64), (0.0, 0.0));
+        for r in [-0.2, -0.01, 0.01, 0.2, 1.0] {
+            let (force, potential) = kratzer_force(&properties, r);
+            assert!(potential > 0.0, "r = {}", r);
+            assert!(force * r < 0.0, "r = {}", r);
+        }
+        let params = SimulationParameters::new(String::from("kratzer"), String::from("H"), 5000.0, 0.5, 10.0);
+        let result = simulate_molecule(&params).unwrap();
+        assert!(result.energy_drift() < 1e-3);
+        assert!(result.distances.iter().all(|&d| (d - 1.4011).abs() < 0.1));
+    }
+
+    // Period: a harmonic run takes 2π / ω per oscillation, from rest at a turning point or from the minimum
+    #[test]
+    fn harmonic_period_is_two_pi_over_omega() {
+        let properties = get_element_properties("H").unwrap();
+        let analytic = 2.0 * std::f64::consts::PI * (properties.m_au as f64 / properties.k_au as f64).sqrt();
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 0.0, 0.1, 300.0);
+        for init_mode in ["thermal_displacement", "thermal_velocity"] {
+            params.set_init_mode(String::from(init_mode));
+            let period = measure_period(&params).unwrap();
+            assert!((period - analytic).abs() < 1e-4 * analytic, "{}: {} vs {}", init_mode, period, analytic);
+        }
+    }
+
+    // Timing: the compute time is only reported when timing is enabled, and is never negative
+    #[test]
+    fn timing_reports_a_nonnegative_compute_time() {
+        let mut params = SimulationParameters::new(String::from("morse"), String::from("H"), 5000.0, 0.5, 300.0);
+        assert_eq!(simulate_molecule(&params).unwrap().compute_ms, None);
+        params.set_timing(true);
+        let compute_ms = simulate_molecule(&params).unwrap().compute_ms.unwrap();
+        assert!(compute_ms >= 0.0 && compute_ms.is_finite());
+    }
+
+    // Classical turning points: ±sqrt(2E / k) for the harmonic model, V(r) = E on both sides for Morse,
+    // and an error once the energy exceeds the well depth
+    #[test]
+    fn turning_points_of_harmonic_and_morse_potentials() {
+        let properties = get_element_properties("H").unwrap();
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 0.0, 0.5, 300.0);
+        let energy = initial_state(&params).unwrap().total_e;
+        let analytic = (2.0 * energy / properties.k_au).sqrt() as f64;
+        assert_eq!(turning_points(&params).unwrap(), vec![-analytic, analytic]);
+
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 0.0, 0.5, 3000.0);
+        let energy = initial_state(&params).unwrap().total_e;
+        let points = turning_points(&params).unwrap();
+        assert!(points[0] < 0.0 && points[1] > -points[0]);
+        for r in points {
+            let potential = morse_force(&properties, r as f32).1;
+            assert!((potential - energy).abs() < 1e-4 * energy, "r = {}: {} vs {}", r, potential, energy);
+        }
+        let hot = SimulationParameters::new(String::from("morse"), String::from("H"), 0.0, 0.5, 1e6);
+        assert!(turning_points(&hot).is_err());
+    }
+
+    // Timestep convergence: the Verlet energy drift shrinks as dt does, about 4 times per halving (O(dt²))
+    #[test]
+    fn energy_drift_decreases_with_the_timestep() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 5000.0, 1.0, 300.0);
+        let drifts = timestep_convergence(&params, &[8.0, 4.0, 2.0, 1.0]).unwrap();
+        assert_eq!(drifts.iter().map(|&(dt, _)| dt).collect::<Vec<f64>>(), vec![8.0, 4.0, 2.0, 1.0]);
+        for pair in drifts.windows(2) {
+            let ratio = pair[0].1 / pair[1].1;
+            assert!((3.0..5.0).contains(&ratio), "dt {} -> {}: ratio {}", pair[0].0, pair[1].0, ratio);
+        }
+    }
+
+    // Zero force constant: an element without k (here H with k removed) is a clean error for every model
+    #[test]
+    fn zero_force_constant_is_an_error() {
+        let properties = ElementProperties { k_au: 0.0, k_si: 0.0, ..get_element_properties("H").unwrap() };
+        for model in ["harmonic", "morse", "kratzer"] {
+            let params = SimulationParameters::new(model.to_string(), String::from("H"), 100.0, 0.5, 300.0);
+            assert!(matches!(
+                simulate_with_properties(&params, properties),
+                Err(SimError::ZeroForceConstant(element)) if element == "H"
+            ), "{}", model);
+        }
+    }
+
+    // RMS velocity: starting with E = k_B T, the mean kinetic energy of the harmonic oscillator is ½ k_B T,
+    // so the RMS velocity is sqrt(k_B T / m); the maximum speed is sqrt(2 k_B T / m) at the minimum
+    #[test]
+    fn harmonic_rms_velocity_is_thermal() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 31416.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let m = get_element_properties("H").unwrap().m_au as f64;
+        let kt = KB as f64 * 300.0 / HARTREE_TO_J as f64;
+        let thermal = (kt / m).sqrt();
+        assert!((result.rms_velocity - thermal).abs() < 0.02 * thermal, "{} vs {}", result.rms_velocity, thermal);
+        assert!((result.max_velocity - 2f64.sqrt() * thermal).abs() < 0.02 * thermal);
+    }
+
+    // Element info: Ar suits only Lennard-Jones, H the bonded models, each in a requested category
+    #[test]
+    fn element_info_lists_the_valid_models() {
+        let argon = get_element_info("Ar").unwrap();
+        assert_eq!(argon.valid_models, &["lennard-jones"]);
+        assert_eq!(argon.category, "noble_gas");
+        assert!(!get_element_info("H").unwrap().valid_models.contains(&"lennard-jones"));
+        for info in ELEMENT_INFO {
+            assert!(["noble_gas", "homonuclear_diatomic", "heteronuclear_diatomic"].contains(&info.category), "{}", info.symbol);
+            for model in info.valid_models {
+                let properties = get_element_properties(info.symbol).unwrap();
+                assert!(validate_model_parameters(model, info.symbol, &properties).is_ok(), "{} {}", info.symbol, model);
+            }
+        }
+    }
+
+    // Summary: the scalars agree with the same quantities computed from the full time series
+    #[test]
+    fn summary_matches_the_full_run() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 3000.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let summary = result.summary();
+        let n = result.times.len();
+        let mean = |series: &[f64]| series.iter().sum::<f64>() / n as f64;
+        assert_eq!(summary.n_samples, n);
+        assert_eq!(summary.final_time, 3000.0);
+        assert!((summary.amplitude - result.peak_displacement()).abs() < 1e-3 * summary.amplitude);
+        let properties = get_element_properties("H").unwrap();
+        let frequency = (properties.k_au as f64 / properties.m_au as f64).sqrt() / (2.0 * std::f64::consts::PI);
+        assert!((summary.frequency.unwrap() - frequency).abs() < 1e-2 * frequency);
+        assert_eq!(summary.mean_potential_energy, mean(&result.potential_energies));
+        assert_eq!(summary.mean_kinetic_energy, mean(&result.kinetic_energies));
+        assert_eq!(summary.mean_total_energy, mean(&result.total_energies));
+        assert_eq!(summary.energy_drift, result.energy_drift());
+        assert_eq!((summary.rms_velocity, summary.max_velocity), (result.rms_velocity, result.max_velocity));
+    }
+
+    // Reflecting wall: a step through the wall ends at the wall with the velocity reversed and the energy
+    // of the step before; over a run the molecule bounces off the wall without losing energy
+    #[test]
+    fn wall_reflects_the_velocity_and_keeps_the_energy() {
+        let properties = get_element_properties("H").unwrap();
+        let force_law = |r: f64| harmonic_force(&properties, r);
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 3000.0, 0.5, 300.0);
+        let amplitude = initial_state(&params).unwrap().displacement.abs() as f64;
+        let wall = -0.5 * amplitude;
+
+        let mut state = initial_state(&params).unwrap().convert::<f64>();
+        let prev_total_e = state.total_e;
+        state.displacement = 1.1 * wall;
+        state.velocity = -1e-3;
+        reflect_at_wall(&mut state, wall, prev_total_e, properties.m_au as f64, &force_law);
+        assert_eq!(state.displacement, wall);
+        assert!(state.velocity > 0.0);
+        assert!((state.total_e - prev_total_e).abs() < 1e-12 * prev_total_e);
+
+        params.set_reflect_at(Some(wall));
+        params.set_preset(Some(String::from("raw")));
+        let result = simulate_molecule(&params).unwrap();
+        let bounces = result.velocities.windows(2).zip(&result.displacements[1..])
+            .filter(|(v, &x)| v[0] < 0.0 && v[1] > 0.0 && (x - wall).abs() < 1e-6 * amplitude)
+            .count();
+        assert!(bounces >= 8, "{} bounces", bounces);
+        assert!(result.displacements.iter().all(|&x| x >= wall - 1e-6 * amplitude));
+        assert!(result.energy_drift() < 1e-3, "{}", result.energy_drift());
+    }
+
+    // Well depth: Morse hydrogen reports the tabulated d_au, Lennard-Jones argon its eps_au; the harmonic
+    // well is infinitely deep. The equilibrium distance is the tabulated bond length
+    #[test]
+    fn well_depth_and_equilibrium_distance_from_the_table() {
+        let params = |model: &str, element: &str| SimulationParameters::new(model.to_string(), element.to_string(), 0.0, 0.5, 300.0);
+        assert_eq!(well_depth(&params("morse", "H")).unwrap(), ELEMENT_PROPERTIES[0].1.d_au as f64);
+        assert_eq!(well_depth(&params("morse", "H")).unwrap(), 1.818446E-01_f32 as f64);
+        assert_eq!(well_depth(&params("lennard-jones", "Ar")).unwrap(), get_element_properties("Ar").unwrap().eps_au as f64);
+        assert_eq!(well_depth(&params("harmonic", "H")).unwrap(), f64::INFINITY);
+        assert_eq!(equilibrium_distance(&params("morse", "H")).unwrap(), 1.4011_f32 as f64);
+    }
+
+    // Random thermal initial conditions: the same seed reproduces the trajectory, another seed does not
+    #[test]
+    fn seeded_random_runs_are_reproducible() {
+        let run = |seed| {
+            let mut params = SimulationParameters::new(String::from("morse"), String::from("H"), 500.0, 0.5, 300.0);
+            params.set_thermal_random(true);
+            params.set_seed(Some(seed));
+            simulate_molecule(&params).unwrap()
+        };
+        let (first, again, other) = (run(42), run(42), run(43));
+        assert_eq!(first.displacements, again.displacements);
+        assert_eq!(first.velocities, again.velocities);
+        assert_ne!(first.displacements, other.displacements);
+        assert_ne!(first.velocities[0], other.velocities[0]);
+    }
+
+    // Ensemble average: with Maxwell-Boltzmann initial conditions the mean kinetic energy approaches
+    // the equipartition value ½ k_B T at every time point as the number of runs grows
+    #[test]
+    fn ensemble_kinetic_energy_approaches_equipartition() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 500.0, 0.5, 300.0);
+        let half_kt = equipartition_energy(300.0, "au").unwrap();
+        let deviation = |n_runs| {
+            let ensemble = ensemble_average(&params, n_runs, 1).unwrap();
+            assert_eq!(ensemble.n_runs, n_runs);
+            let deviations: Vec<f64> = ensemble.mean_kinetic_energies.iter().map(|ke| (ke - half_kt).abs() / half_kt).collect();
+            deviations.iter().sum::<f64>() / deviations.len() as f64
+        };
+        let (few, many) = (deviation(16), deviation(1024));
+        assert!(many < 0.5 * few, "{} vs {}", many, few);
+        assert!(many < 0.1, "{}", many);
+    }
+
+    // Step count: a vanishingly small timestep is rejected before any allocation
+    #[test]
+    fn tiny_timestep_is_too_many_steps() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 1.0, 1e-20, 300.0);
+        match simulate_molecule(&params) {
+            Err(SimError::TooManySteps { steps, max_steps }) => {
+                assert!((steps - 1e20).abs() / 1e20 < 1e-6, "{}", steps);
+                assert_eq!(max_steps, params.max_steps());
+            }
+            other => panic!("expected TooManySteps, got {:?}", other.map(|r| r.times.len())),
+        }
+    }
+
+    // Force curve: F = -k r for the harmonic model, and a restoring force that changes sign at the
+    // minimum for the Morse and Lennard-Jones models
+    #[test]
+    fn force_curve_is_restoring_about_the_minimum() {
+        let harmonic = SimulationParameters::new(String::from("harmonic"), String::from("H"), 10.0, 0.1, 300.0);
+        let k = get_element_properties("H").unwrap().k_au as f64;
+        let curve = force_curve(&harmonic, -0.5, 0.5, 11).unwrap();
+        assert_eq!(curve.len(), 11);
+        assert_eq!((curve[0].0, curve[10].0), (-0.5, 0.5));
+        for &(r, f) in &curve {
+            assert!((f + k * r).abs() < 1e-6, "{} {}", r, f);
+        }
+
+        for (model, element, range) in [("morse", "H", 0.3), ("lennard-jones", "Ar", 0.5)] {
+            let params = SimulationParameters::new(String::from(model), String::from(element), 10.0, 0.1, 10.0);
+            let curve = force_curve(&params, -range, range, 21).unwrap();
+            for &(r, f) in &curve {
+                if r < -1e-9 {
+                    assert!(f > 0.0, "{} {} {}", model, r, f);
+                } else if r > 1e-9 {
+                    assert!(f < 0.0, "{} {} {}", model, r, f);
+                }
+            }
+        }
+
+        assert!(force_curve(&harmonic, 0.5, -0.5, 11).is_err());
+        assert!(force_curve(&harmonic, -0.5, 0.5, 1).is_err());
+    }
+
+    // Element constants: the serialized constants of hydrogen match the table, and unknown symbols are an error
+    #[test]
+    fn serialized_hydrogen_mass_matches_the_table() {
+        let json = serde_json::to_value(get_element_constants("H").unwrap()).unwrap();
+        assert_eq!(json["symbol"], "H");
+        assert_eq!(json["m_au"].as_f64().unwrap() as f32, get_element_properties("H").unwrap().m_au);
+        assert_eq!(json["m_au"].as_f64().unwrap() as f32, 911.44);
+        assert!(matches!(get_element_constants("Xx"), Err(SimError::UnknownElement { .. })));
+    }
+
+    // Vibrational wavenumber: hydrogen vibrates at a few thousand cm⁻¹ (4401 cm⁻¹ for H2), the same for
+    // both models, and the Lennard-Jones model has no harmonic force constant
+    #[test]
+    fn hydrogen_vibrational_wavenumber() {
+        let params = |model: &str| SimulationParameters::new(model.to_string(), String::from("H"), 10.0, 0.1, 300.0);
+        let harmonic = vibrational_wavenumber(&params("harmonic")).unwrap();
+        assert!((4000.0..4800.0).contains(&harmonic), "{}", harmonic);
+        assert_eq!(vibrational_wavenumber(&params("morse")).unwrap(), harmonic);
+        let argon = SimulationParameters::new(String::from("lennard-jones"), String::from("Ar"), 10.0, 0.1, 10.0);
+        assert!(vibrational_wavenumber(&argon).is_err());
+    }
+
+    // Anharmonicity: the Morse frequency drops with amplitude, more so at higher temperature, and the
+    // harmonic oscillator has none to estimate
+    #[test]
+    fn morse_anharmonicity_grows_with_temperature() {
+        let params = |model: &str, temperature| SimulationParameters::new(model.to_string(), String::from("H"), 100.0, 0.05, temperature);
+        let warm = anharmonicity(&params("morse", 1000.0)).unwrap();
+        let hot = anharmonicity(&params("morse", 5000.0)).unwrap();
+        assert!(warm > 0.0, "{}", warm);
+        assert!(hot > warm, "{} vs {}", hot, warm);
+        assert!(matches!(anharmonicity(&params("harmonic", 1000.0)), Err(SimError::NotApplicable { .. })));
+    }
+
+    // Columns: each column of the flat buffer, located by the layout, matches the serialized series
+    #[test]
+    fn flat_columns_match_the_serialized_series() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 200.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let buffer = result.to_columns();
+        let layout = result.column_layout();
+        assert_eq!(layout.n_rows, result.times.len());
+        assert_eq!(buffer.len(), layout.n_rows * layout.columns.len());
+        let json = serde_json::to_value(&result).unwrap();
+        for (i, name) in layout.columns.iter().enumerate() {
+            let column = &buffer[i * layout.n_rows..(i + 1) * layout.n_rows];
+            let series = json[*name].as_array().unwrap();
+            assert_eq!(series.len(), layout.n_rows, "{}", name);
+            for (a, b) in column.iter().zip(series) {
+                let b = b.as_f64().unwrap();
+                assert!((a - b).abs() <= 1e-15 * a.abs().max(1e-300), "{}: {} vs {}", name, a, b);
+            }
+        }
+    }
+
+    // Sampling: uniform samples are evenly spaced, log samples have a constant ratio of successive times,
+    // and turning-point samples of a harmonic run are half a period apart
+    #[test]
+    fn sampling_strategies_space_the_samples() {
+        let run = |strategy: &str| {
+            let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 10000.0, 0.5, 300.0);
+            params.set_sampling(strategy.to_string());
+            simulate_molecule(&params).unwrap().times
+        };
+
+        let uniform = run("uniform");
+        assert!(uniform.len() <= 2001);
+        let spacing = uniform[1] - uniform[0];
+        assert!(spacing > 0.5);
+        assert!(uniform.windows(2).all(|w| (w[1] - w[0] - spacing).abs() < 1e-9));
+
+        let log = run("log");
+        assert_eq!((log[0], *log.last().unwrap()), (0.0, 10000.0));
+        let ratio = (20000.0f64.ln() / 1999.0).exp();
+        for w in log.windows(2).filter(|w| w[0] > 50.0) {
+            assert!((w[1] / w[0] / ratio - 1.0).abs() < 1e-2, "{} {}", w[0], w[1]);
+        }
+
+        let turning_points = run("turning_points");
+        let half_period = std::f64::consts::PI * (911.44f64 / 0.3665).sqrt();
+        let interior = &turning_points[1..turning_points.len() - 1];
+        assert!(interior.len() > 50);
+        for w in interior.windows(2) {
+            assert!((w[1] - w[0] - half_period).abs() < 1.0, "{}", w[1] - w[0]);
+        }
+    }
+
+    // Autocorrelation: 1 at lag 0, then oscillating at the vibrational frequency of a harmonic run
+    // (first zero at a quarter period, minimum near half a period, back up after a full period)
+    #[test]
+    fn harmonic_autocorrelation_oscillates() {
+        let period = 2.0 * std::f64::consts::PI * (911.44f64 / 0.3665).sqrt();
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 10.0 * period, 0.5, 300.0);
+        params.set_preset(Some(String::from("raw")));
+        let correlation = simulate_molecule(&params).unwrap().autocorrelation();
+        assert!((correlation[0] - 1.0).abs() < 1e-12);
+        let lag = |t: f64| (t / 0.5).round() as usize;
+        let first_zero = correlation.iter().position(|&c| c < 0.0).unwrap();
+        assert!(first_zero.abs_diff(lag(period / 4.0)) <= 2, "{}", first_zero);
+        assert!(correlation[lag(period / 2.0)] < -0.8);
+        assert!(correlation[lag(period)] > 0.8);
+    }
+
+    // Warnings: a run capped at max_steps ends early and says so in its (serialized) warnings,
+    // while the same run without cap_steps is an error
+    #[test]
+    fn capped_step_count_is_a_warning() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 1000.0, 0.5, 300.0);
+        params.set_max_steps(100.0);
+        assert!(matches!(simulate_molecule(&params), Err(SimError::TooManySteps { .. })));
+        params.set_cap_steps(true);
+        params.set_preset(Some(String::from("raw")));
+        let result = simulate_molecule(&params).unwrap();
+        assert_eq!(result.times.len(), 101);
+        assert!((result.times[100] - 50.0).abs() < 1e-9);
+        assert!(result.warnings.iter().any(|w| w.starts_with("Step count capped at 1e2")), "{:?}", result.warnings);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["warnings"].as_array().unwrap().len(), result.warnings.len());
+    }
+
+    // JSON fields: only the requested arrays are serialized, and an unknown field name is an error
+    #[test]
+    fn json_with_only_the_requested_fields() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 100.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let fields = [String::from("times"), String::from("displacements")];
+        let json: serde_json::Value = serde_json::from_str(&result.to_json_fields(&fields).unwrap()).unwrap();
+        let object = json.as_object().unwrap();
+        assert_eq!(object.len(), 2);
+        assert_eq!(object["times"].as_array().unwrap().len(), result.times.len());
+        assert_eq!(object["displacements"].as_array().unwrap().len(), result.displacements.len());
+        for absent in ["velocities", "distances", "potential_energies", "kinetic_energies", "total_energies", "virial"] {
+            assert!(!object.contains_key(absent), "{}", absent);
+        }
+        assert!(matches!(
+            result.to_json_fields(&[String::from("times"), String::from("speed")]),
+            Err(SimError::UnknownOption { option: "result field", .. })
+        ));
+    }
+
+    // Isotopes: on the same potential, lighter masses oscillate faster (H2, D2, T2 reduced masses,
+    // with frequencies in the ratio sqrt(m2 / m1)), and a non-positive mass is rejected
+    #[test]
+    fn lighter_isotopes_oscillate_faster() {
+        let masses = [911.44, 1822.89, 2734.33];
+        let runs = isotope_comparison("H", &masses, 6000.0, 0.5, 300.0).unwrap();
+        let counts: Vec<u32> = runs.iter().map(|run| run.result.oscillation_count()).collect();
+        assert_eq!(runs.iter().map(|run| run.mass_au).collect::<Vec<_>>(), masses);
+        assert!(counts[0] > counts[1] && counts[1] > counts[2], "{:?}", counts);
+        let ratio = counts[0] as f64 / counts[1] as f64;
+        assert!((ratio - 2f64.sqrt()).abs() < 0.1, "{}", ratio);
+        assert!(isotope_comparison("H", &[911.44, 0.0], 100.0, 0.5, 300.0).is_err());
+    }
+
+    // Bound classification: hydrogen in the Morse well is bound at 300 K and unbound above its
+    // dissociation energy (D ≈ 57 000 K), and the harmonic model is always bound so it is an error
+    #[test]
+    fn hydrogen_morse_is_bound_only_at_low_temperature() {
+        let params = |model: &str, temperature| SimulationParameters::new(model.to_string(), String::from("H"), 100.0, 0.1, temperature);
+        assert!(is_bound(&params("morse", 300.0)).unwrap());
+        assert!(!is_bound(&params("morse", 100000.0)).unwrap());
+        assert!(matches!(is_bound(&params("harmonic", 300.0)), Err(SimError::NotApplicable { .. })));
+    }
+
+    // Interpolation: at a midpoint between two samples every series is the average of its neighbours,
+    // at any point the manual linear interpolation, and outside the run the end samples
+    #[test]
+    fn sample_at_interpolates_linearly() {
+        let params = SimulationParameters::new(String::from("morse"), String::from("H"), 100.0, 0.5, 300.0);
+        let result = simulate_molecule(&params).unwrap();
+        let (i, j) = (40, 41);
+        let midpoint = result.sample_at(0.5 * (result.times[i] + result.times[j])).unwrap();
+        let average = |series: &[f64]| 0.5 * (series[i] + series[j]);
+        assert!((midpoint.displacement - average(&result.displacements)).abs() < 1e-12);
+        assert!((midpoint.velocity - average(&result.velocities)).abs() < 1e-12);
+        assert!((midpoint.potential_e - average(&result.potential_energies)).abs() < 1e-12);
+        assert!((midpoint.kinetic_e - average(&result.kinetic_energies)).abs() < 1e-12);
+        assert!((midpoint.total_e - average(&result.total_energies)).abs() < 1e-12);
+
+        let t = result.times[i] + 0.3 * (result.times[j] - result.times[i]);
+        let expected = result.displacements[i] + 0.3 * (result.displacements[j] - result.displacements[i]);
+        assert!((result.sample_at(t).unwrap().displacement - expected).abs() < 1e-12);
+
+        let after = result.sample_at(1e6).unwrap();
+        assert_eq!((after.time, after.displacement), (100.0, *result.displacements.last().unwrap()));
+        assert_eq!(result.sample_at(-1.0).unwrap().displacement, result.displacements[0]);
+        assert!(result.sample_at(f64::NAN).is_err());
+    }
+
+    // Lennard-Jones interpolation: at the geometric mean of the argon and mercury masses the parameters
+    // are the geometric means of theirs, and they are clamped outside the tabulated masses
+    #[test]
+    fn lennard_jones_interpolation_at_a_midpoint() {
+        let argon = get_element_properties("Ar").unwrap();
+        let mercury = get_element_properties("Hg").unwrap();
+        let mass = (argon.m_au as f64 * mercury.m_au as f64).sqrt();
+        let midpoint = interpolate_lennard_jones(mass).unwrap();
+        let geometric_mean = |a: f32, b: f32| (a as f64 * b as f64).sqrt();
+        assert!((midpoint.eps_au / geometric_mean(argon.eps_au, mercury.eps_au) - 1.0).abs() < 1e-9);
+        assert!((midpoint.rstr_au / geometric_mean(argon.rstr_au, mercury.rstr_au) - 1.0).abs() < 1e-9);
+        let heavy = interpolate_lennard_jones(10.0 * mercury.m_au as f64).unwrap();
+        assert_eq!(heavy.eps_au, mercury.eps_au as f64);
+        assert!(interpolate_lennard_jones(-1.0).is_err());
+    }
+
+    // Random seed: natively an unseeded random run seeds from the system clock (no JavaScript needed),
+    // so two unseeded runs start differently
+    #[test]
+    fn unseeded_random_runs_work_natively() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 10.0, 0.5, 300.0);
+        params.set_thermal_random(true);
+        let first = simulate_molecule(&params).unwrap();
+        let second = simulate_molecule(&params).unwrap();
+        assert!(first.displacements.iter().chain(&first.velocities).all(|v| v.is_finite()));
+        assert_ne!((first.displacements[0], first.velocities[0]), (second.displacements[0], second.velocities[0]));
+    }
+
+    // Continuation: a run above the sample limit is subsampled, so its last sample is not the end of
+    // the run and continuing it is an error; the same run with the "raw" preset continues seamlessly
+    #[test]
+    fn subsampled_result_cannot_be_continued() {
+        let mut part = SimulationParameters::new(String::from("morse"), String::from("H"), 5000.0, 0.5, 300.0);
+        let subsampled = simulate_molecule(&part).unwrap();
+        assert!(subsampled.times.len() < 10001);
+        assert!(matches!(simulate_continue(&subsampled, &part), Err(SimError::InvalidInput(_))));
+
+        part.set_preset(Some(String::from("raw")));
+        let first = simulate_molecule(&part).unwrap();
+        assert_eq!(first.times.len(), 10001);
+        let second = simulate_continue(&first, &part).unwrap();
+        let mut long = SimulationParameters::new(String::from("morse"), String::from("H"), 10000.0, 0.5, 300.0);
+        long.set_preset(Some(String::from("raw")));
+        let whole = simulate_molecule(&long).unwrap();
+        assert_eq!(second.times.len(), 10001);
+        for (i, x) in second.displacements.iter().enumerate() {
+            assert!((x - whole.displacements[10000 + i]).abs() < 1e-5, "{}", i);
+        }
+    }
+
+    // Harmonic residuals: an unseeded random start is compared against the analytic solution of that
+    // same start (the residual stays small instead of being the gap between two oscillations)
+    #[test]
+    fn harmonic_residuals_of_an_unseeded_random_start() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 5000.0, 0.5, 300.0);
+        params.set_thermal_random(true);
+        let residuals = harmonic_residuals(&params).unwrap();
+        let amplitude = thermal_amplitude(&params).unwrap();
+        assert_eq!(residuals[0].1, 0.0);
+        let peak = residuals.iter().fold(0.0, |a, &(_, r)| f64::max(a, r.abs()));
+        assert!(peak < 0.01 * amplitude, "residual {} against amplitude {}", peak, amplitude);
+    }
+
+    // Kratzer initial state: a start energy above the well depth (H above ~5.5e4 K) has no finite
+    // stretched displacement and is reported as an error instead of returning inf/NaN data
+    #[test]
+    fn kratzer_start_above_the_well_depth_is_an_error() {
+        let params = SimulationParameters::new(String::from("kratzer"), String::from("H"), 100.0, 0.5, 1e5);
+        assert!(matches!(initial_state(&params), Err(SimError::InvalidInput(_))));
+        assert!(matches!(simulate_molecule(&params), Err(SimError::InvalidInput(_))));
+        let params = SimulationParameters::new(String::from("kratzer"), String::from("H"), 100.0, 0.5, 5e4);
+        let result = simulate_molecule(&params).unwrap();
+        assert!(result.displacements.iter().chain(&result.total_energies).all(|x| x.is_finite()));
+    }
+
+    // Period: measured with the run's own stepping (a wall at -A/2 cuts the harmonic period to 2/3,
+    // substeps match the finer timestep, f64 agrees with f32), and a start at rest at the minimum has none
+    #[test]
+    fn measured_period_follows_the_run_settings() {
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 0.0, 0.5, 300.0);
+        let period = measure_period(&params).unwrap();
+        let amplitude = initial_state(&params).unwrap().displacement as f64;
+        params.set_reflect_at(Some(-0.5 * amplitude));
+        let walled = measure_period(&params).unwrap();
+        assert!((walled / period - 2.0 / 3.0).abs() < 1e-2, "{} against {}", walled, period);
+        params.set_reflect_at(None);
+        params.set_substeps(4);
+        let substepped = measure_period(&params).unwrap();
+        params.set_substeps(1);
+        params.timestep = 0.125;
+        assert!((substepped - measure_period(&params).unwrap()).abs() < 1e-3 * period);
+        params.set_precision(String::from("f64"));
+        assert!((measure_period(&params).unwrap() - substepped).abs() < 1e-3 * period);
+        let at_rest = SimulationParameters::new(String::from("morse"), String::from("H"), 0.0, 0.5, 0.0);
+        assert!(matches!(measure_period(&at_rest), Err(SimError::NoConvergence(_))));
+    }
+}
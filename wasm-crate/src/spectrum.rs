@@ -0,0 +1,124 @@
+/*
+Module for spectral analysis of simulation results
+
+Contains:
+ - Spectrum struct:
+    - power of the displacement signal at each frequency (cycles per atomic unit of time)
+ - power_spectrum function:
+    - removes the mean, zero-pads the displacements to a power of two and Fourier transforms them
+ - fft function:
+    - in-place iterative radix-2 fast Fourier transform
+ - tests module (native only):
+    - spectrum peak of a harmonic run (zero-padded to a power of two) at the analytic frequency
+    - FFT of a single cosine
+*/
+
+use serde::Serialize;
+
+use crate::error::SimError;
+use crate::sim::SimulationResult;
+
+// Define result struct for a power spectrum
+#[derive(Serialize)]
+pub struct Spectrum {
+    pub frequencies: Vec<f64>,  // Frequencies from zero to the Nyquist frequency (cycles per atomic unit of time)
+    pub power: Vec<f64>,        // Power |X(f)|² / n of the displacement signal at each frequency
+}
+
+// Function to compute the power spectrum of the displacements (assumes uniformly spaced samples)
+pub fn power_spectrum(result: &SimulationResult) -> Result<Spectrum, SimError> {
+    let n = result.displacements.len();
+    if n < 2 {
+        return Err(SimError::InvalidInput(String::from("Power spectrum needs at least 2 samples")));
+    }
+    let spacing = result.times[1] - result.times[0];
+    if !(spacing > 0.0 && spacing.is_finite()) {
+        return Err(SimError::InvalidInput(String::from("Power spectrum needs increasing sample times")));
+    }
+    
+    // Remove the mean and zero-pad to the next power of two
+    let size = n.next_power_of_two();
+    let mean = result.displacements.iter().sum::<f64>() / n as f64;
+    let mut re: Vec<f64> = result.displacements.iter().map(|d| d - mean).collect();
+    re.resize(size, 0.0);
+    let mut im = vec![0.0; size];
+    fft(&mut re, &mut im);
+    
+    // Keep the non-negative frequencies (the signal is real, so the rest mirror them)
+    let frequencies = (0..=size / 2).map(|k| k as f64 / (size as f64 * spacing)).collect();
+    let power = (0..=size / 2).map(|k| (re[k] * re[k] + im[k] * im[k]) / n as f64).collect();
+    Ok(Spectrum { frequencies, power })
+}
+
+// Function to Fourier transform (re, im) in place (length must be a power of two)
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    
+    // Reorder the samples into bit-reversed order
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    
+    // Combine transforms of doubling length (butterflies)
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::sim::{simulate_molecule, SimulationParameters};
+
+    // Power spectrum: the peak of a harmonic run is at the analytic frequency sqrt(k / m) / 2π
+    // (to within the frequency resolution)
+    #[test]
+    fn harmonic_spectrum_peaks_at_the_vibrational_frequency() {
+        let frequency = (0.3665f64 / 911.44).sqrt() / (2.0 * std::f64::consts::PI);
+        let mut params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 30.0 / frequency, 0.5, 300.0);
+        params.set_preset(Some(String::from("raw")));
+        let spectrum = power_spectrum(&simulate_molecule(&params).unwrap()).unwrap();
+        assert_eq!(spectrum.frequencies.len(), spectrum.power.len());
+        let peak = (0..spectrum.power.len()).max_by(|&a, &b| spectrum.power[a].total_cmp(&spectrum.power[b])).unwrap();
+        let resolution = spectrum.frequencies[1] - spectrum.frequencies[0];
+        assert!((spectrum.frequencies[peak] - frequency).abs() <= resolution, "{} vs {}", spectrum.frequencies[peak], frequency);
+    }
+
+    // FFT: a cosine of 5 cycles over 64 samples transforms to two spikes of height n / 2
+    #[test]
+    fn fft_of_a_cosine() {
+        let n = 64;
+        let mut re: Vec<f64> = (0..n).map(|i| (2.0 * std::f64::consts::PI * 5.0 * i as f64 / n as f64).cos()).collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        for k in 0..n {
+            let magnitude = re[k].hypot(im[k]);
+            let expected = if k == 5 || k == n - 5 { n as f64 / 2.0 } else { 0.0 };
+            assert!((magnitude - expected).abs() < 1e-9, "{} {}", k, magnitude);
+        }
+    }
+}
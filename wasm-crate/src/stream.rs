@@ -0,0 +1,183 @@
+/*
+Module for stepwise (streaming) simulation, e.g. for animations with play/pause
+
+Contains:
+ - Simulation struct:
+    - holds the current state of one run and advances it on request (step)
+    - timestep and temperature can be changed mid-run (set_timestep, set_temperature)
+    - reset returns the run to its initial state
+    - getters for the current time, displacement, velocity and energies
+ - tests module (native only):
+    - reset returning to the initial state
+    - a changed timestep applying to later steps
+*/
+
+use wasm_bindgen::prelude::*;
+
+use crate::error::SimError;
+use crate::sim::{
+    init_model, reflect_at_wall, rescale_to_energy, verlet_step, ElementProperties, ForceLaw, SimulationParameters,
+    SimulationState, HARTREE_TO_J, KB,
+};
+
+// Define struct for a simulation advanced step by step from JavaScript
+#[wasm_bindgen]
+pub struct Simulation {
+    properties: ElementProperties,     // Constants of the simulated element
+    force_law: ForceLaw,               // Force law of the simulated model
+    initial_state: SimulationState,    // State to return to on reset
+    state: SimulationState,            // Current state
+    timestep: f32,                     // Time step for subsequent steps
+    temperature: f64,                  // Current temperature
+    reflect_at: Option<f32>,           // Optional hard reflecting wall
+    hard_core: Option<f32>,            // Displacement of the wall at zero distance (if the hard core is enabled)
+}
+
+#[wasm_bindgen]
+impl Simulation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(params: &SimulationParameters) -> Result<Simulation, JsValue> {
+        let (properties, initial_state, force_law) = init_model(params)?;
+        let timestep = params.timestep();
+        if !(timestep > 0.0 && timestep.is_finite()) {
+            return Err(SimError::InvalidTimestep(timestep).into());
+        }
+        
+        Ok(Simulation {
+            properties,
+            force_law,
+            state: initial_state.clone(),
+            initial_state,
+            timestep: timestep as f32,
+            temperature: params.initial_temperature(),
+            reflect_at: params.reflect_at().map(|wall| wall as f32),
+            hard_core: params.hard_core().then_some(-properties.rstr_au),
+        })
+    }
+    
+    // Advance the simulation by `n_steps` timesteps
+    pub fn step(&mut self, n_steps: u32) {
+        let properties = self.properties;
+        let force_law = self.force_law;
+        let force_law = |r| force_law(&properties, r);
+        for _ in 0..n_steps {
+            let prev_total_e = self.state.total_e;
+            verlet_step(&mut self.state, properties.m_au, &force_law, self.timestep);
+            if let Some(wall) = self.reflect_at {
+                reflect_at_wall(&mut self.state, wall, prev_total_e, properties.m_au, &force_law);
+            }
+            if let Some(core) = self.hard_core {
+                reflect_at_wall(&mut self.state, core, prev_total_e, properties.m_au, &force_law);
+            }
+        }
+    }
+    
+    // Change the timestep used by subsequent steps
+    pub fn set_timestep(&mut self, timestep: f64) -> Result<(), JsValue> {
+        if !(timestep > 0.0 && timestep.is_finite()) {
+            return Err(SimError::InvalidTimestep(timestep).into());
+        }
+        self.timestep = timestep as f32;
+        Ok(())
+    }
+    
+    // Change the temperature by rescaling the velocity so the total energy becomes k_B T
+    // (the energy the initial conditions are built from). If the potential energy alone
+    // already exceeds k_B T the molecule is stopped where it is.
+    pub fn set_temperature(&mut self, temperature: f64) -> Result<(), JsValue> {
+        if !(temperature >= 0.0 && temperature.is_finite()) {
+            return Err(SimError::InvalidTemperature(temperature).into());
+        }
+        rescale_to_energy(&mut self.state, self.properties.m_au, KB * temperature as f32 / HARTREE_TO_J);
+        self.temperature = temperature;
+        Ok(())
+    }
+    
+    // Return the simulation to its initial state
+    pub fn reset(&mut self) {
+        self.state = self.initial_state.clone();
+    }
+    
+    // Getters for the current state
+    #[wasm_bindgen(getter)]
+    pub fn time(&self) -> f64 {
+        self.state.time as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn displacement(&self) -> f64 {
+        self.state.displacement as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn velocity(&self) -> f64 {
+        self.state.velocity as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn potential_energy(&self) -> f64 {
+        self.state.potential_e as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn kinetic_energy(&self) -> f64 {
+        self.state.kinetic_e as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn total_energy(&self) -> f64 {
+        self.state.total_e as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn timestep(&self) -> f64 {
+        self.timestep as f64
+    }
+    
+    #[wasm_bindgen(getter)]
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    fn hydrogen() -> SimulationParameters {
+        SimulationParameters::new(String::from("morse"), String::from("H"), 100.0, 0.1, 300.0)
+    }
+
+    // Reset: stepping away and resetting restores the initial time, displacement and velocity
+    #[test]
+    fn reset_restores_the_initial_state() {
+        let mut sim = Simulation::new(&hydrogen()).unwrap();
+        let (x0, v0) = (sim.displacement(), sim.velocity());
+        sim.step(250);
+        assert!(sim.time() > 0.0);
+        assert_ne!(sim.displacement(), x0);
+        sim.reset();
+        assert_eq!(sim.time(), 0.0);
+        assert_eq!(sim.displacement(), x0);
+        assert_eq!(sim.velocity(), v0);
+    }
+
+    // Timestep: a new timestep is used by subsequent steps only
+    #[test]
+    fn set_timestep_applies_to_later_steps() {
+        let mut sim = Simulation::new(&hydrogen()).unwrap();
+        sim.step(10);
+        let t = sim.time();
+        assert!((t - 1.0).abs() < 1e-5, "{}", t);
+        sim.set_timestep(0.5).unwrap();
+        assert_eq!(sim.timestep(), 0.5);
+        sim.step(10);
+        assert!((sim.time() - t - 5.0).abs() < 1e-4, "{}", sim.time());
+
+        // Differs from a run that kept the original timestep
+        let mut reference = Simulation::new(&hydrogen()).unwrap();
+        reference.step(20);
+        assert!((reference.time() - 2.0).abs() < 1e-5);
+        assert_ne!(reference.displacement(), sim.displacement());
+    }
+}
@@ -0,0 +1,196 @@
+/*
+Module for simulation of user-supplied (tabulated) potentials
+
+Contains:
+ - CubicSpline struct:
+    - natural cubic spline through sampled (r, V) points
+    - evaluates the potential and its derivative (force = -dV/dr) at any displacement
+ - simulate_tabulated function:
+    - validates the table, builds the spline and integrates it with the Velocity Verlet algorithm
+    - starts at rest at the displacement (right of the minimum) where V - V_min = k_B T
+ - tests module (native only):
+    - sampled harmonic potential against the analytic harmonic run
+    - validation of mismatched, non-increasing and too short tables
+*/
+
+use crate::error::SimError;
+use crate::sim::{simulate_model, SimulationParameters, SimulationResult, SimulationState, HARTREE_TO_J, KB};
+
+// Natural cubic spline through sampled points
+pub struct CubicSpline {
+    x: Vec<f64>,   // Sample positions (strictly increasing)
+    y: Vec<f64>,   // Sample values
+    m: Vec<f64>,   // Second derivatives at the sample positions
+}
+
+impl CubicSpline {
+    pub fn new(x: Vec<f64>, y: Vec<f64>) -> CubicSpline {
+        // Solve the tridiagonal system for the second derivatives (zero at both ends)
+        let n = x.len();
+        let mut m = vec![0.0; n];
+        if n > 2 {
+            let mut c_prime = vec![0.0; n];
+            let mut d_prime = vec![0.0; n];
+            for i in 1..n - 1 {
+                let h0 = x[i] - x[i - 1];
+                let h1 = x[i + 1] - x[i];
+                let a = h0 / 6.0;
+                let b = (h0 + h1) / 3.0;
+                let c = h1 / 6.0;
+                let d = (y[i + 1] - y[i]) / h1 - (y[i] - y[i - 1]) / h0;
+                let denom = b - a * c_prime[i - 1];
+                c_prime[i] = c / denom;
+                d_prime[i] = (d - a * d_prime[i - 1]) / denom;
+            }
+            for i in (1..n - 1).rev() {
+                m[i] = d_prime[i] - c_prime[i] * m[i + 1];
+            }
+        }
+        CubicSpline { x, y, m }
+    }
+
+    // Returns (value, derivative) at `r`, extending linearly beyond the table ends
+    pub fn evaluate(&self, r: f64) -> (f64, f64) {
+        let n = self.x.len();
+        if r <= self.x[0] || r >= self.x[n - 1] {
+            let i = if r <= self.x[0] { 0 } else { n - 2 };
+            let end = if r <= self.x[0] { 0 } else { n - 1 };
+            let slope = self.segment(i, self.x[end]).1;
+            return (self.y[end] + slope * (r - self.x[end]), slope);
+        }
+
+        // Find the segment containing r
+        let i = match self.x.binary_search_by(|x| x.partial_cmp(&r).unwrap()) {
+            Ok(i) => i.min(n - 2),
+            Err(i) => i - 1,
+        };
+        self.segment(i, r)
+    }
+
+    // Returns (value, derivative) of the cubic on segment [x_i, x_i+1] at `r`
+    fn segment(&self, i: usize, r: f64) -> (f64, f64) {
+        let h = self.x[i + 1] - self.x[i];
+        let a = (self.x[i + 1] - r) / h;
+        let b = (r - self.x[i]) / h;
+        let value = a * self.y[i] + b * self.y[i + 1]
+            + ((a.powi(3) - a) * self.m[i] + (b.powi(3) - b) * self.m[i + 1]) * h * h / 6.0;
+        let derivative = (self.y[i + 1] - self.y[i]) / h
+            - (3.0 * a * a - 1.0) / 6.0 * h * self.m[i]
+            + (3.0 * b * b - 1.0) / 6.0 * h * self.m[i + 1];
+        (value, derivative)
+    }
+}
+
+// Function to simulate a tabulated potential (only duration, timestep, temperature, sampling and smoothing are used from `params`)
+pub fn simulate_tabulated(
+    r_values: Vec<f64>,
+    v_values: Vec<f64>,
+    mass_au: f64,
+    params: &SimulationParameters
+) -> Result<SimulationResult, SimError> {
+    // Validate the table
+    if r_values.len() != v_values.len() {
+        return Err(SimError::InvalidInput(format!(
+            "Tabulated potential has {} r values but {} V values", r_values.len(), v_values.len()
+        )));
+    }
+    if r_values.len() < 2 {
+        return Err(SimError::InvalidInput(String::from("Tabulated potential needs at least 2 points")));
+    }
+    if r_values.iter().chain(v_values.iter()).any(|v| !v.is_finite()) {
+        return Err(SimError::NonFinite("Tabulated potential"));
+    }
+    if r_values.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(SimError::InvalidInput(String::from("Tabulated r values must be strictly increasing")));
+    }
+    if !(mass_au > 0.0 && mass_au.is_finite()) {
+        return Err(SimError::InvalidInput(String::from("Mass must be positive")));
+    }
+
+    // Find the minimum of the tabulated potential
+    let (i_min, v_min) = v_values.iter().enumerate()
+        .fold((0, f64::INFINITY), |(i_a, a), (i_b, &b)| if b < a { (i_b, b) } else { (i_a, a) });
+    let spline = CubicSpline::new(r_values, v_values);
+
+    // Find the initial displacement right of the minimum with V - V_min = k_B T
+    let e0_au = (KB * params.temperature() as f32 / HARTREE_TO_J) as f64;
+    let r0 = initial_displacement(&spline, i_min, v_min + e0_au)?;
+    let (v0, dv0) = spline.evaluate(r0);
+
+    let initial_sim_state = SimulationState {
+        time: 0.0,
+        displacement: r0 as f32,
+        force: -dv0 as f32,
+        acceleration: (-dv0 / mass_au) as f32,
+        velocity: 0.0,
+        kinetic_e: 0.0,
+        potential_e: v0 as f32,
+        total_e: v0 as f32,
+    };
+
+    // Run the simulation with force = -dV/dr from the spline
+    // (the table is in absolute distances, so the displacement already is the distance)
+    let force_law = |r: f32| {
+        let (v, dv) = spline.evaluate(r as f64);
+        (-dv as f32, v as f32)
+    };
+    let sim_result = simulate_model(initial_sim_state, mass_au as f32, force_law, 0.0, params)?;
+
+    // Return the (sampled, optionally smoothed) simulation result
+    sim_result.finish(params, None)
+}
+
+// Helper function to find where the spline reaches `energy` to the right of the sample `i_min` (bisection)
+fn initial_displacement(spline: &CubicSpline, i_min: usize, energy: f64) -> Result<f64, SimError> {
+    let n = spline.x.len();
+    if spline.y[i_min] >= energy {
+        return Ok(spline.x[i_min]);
+    }
+    for i in i_min..n - 1 {
+        if spline.y[i + 1] < energy {
+            continue;
+        }
+        let (mut lo, mut hi) = (spline.x[i], spline.x[i + 1]);
+        for _ in 0..60 {
+            let mid = 0.5 * (lo + hi);
+            if spline.evaluate(mid).0 < energy { lo = mid } else { hi = mid }
+        }
+        return Ok(0.5 * (lo + hi));
+    }
+    Err(SimError::InvalidInput(String::from("Thermal energy exceeds the range of the tabulated potential")))
+}
+
+// Native unit tests of the tabulated potential (run with `cargo test`)
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::sim::{get_element_constants, simulate_molecule};
+
+    // Sampled harmonic potential: the spline run follows the built-in harmonic run of H
+    // (same mass, same starting energy k_B T) to well within a percent of the amplitude
+    #[test]
+    fn sampled_harmonic_potential_matches_the_harmonic_run() {
+        let constants = get_element_constants("H").unwrap();
+        let r_values: Vec<f64> = (0..=200).map(|i| -0.5 + 0.005 * i as f64).collect();
+        let v_values: Vec<f64> = r_values.iter().map(|r| 0.5 * constants.k_au as f64 * r * r).collect();
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 1000.0, 0.5, 300.0);
+        let tabulated = simulate_tabulated(r_values, v_values, constants.m_au as f64, &params).unwrap();
+        let analytic = simulate_molecule(&params).unwrap();
+        assert_eq!(tabulated.times.len(), analytic.times.len());
+        let amplitude = analytic.displacements[0].abs();
+        for (x_tab, x) in tabulated.displacements.iter().zip(&analytic.displacements) {
+            assert!((x_tab - x).abs() < 1e-2 * amplitude, "{} vs {}", x_tab, x);
+        }
+    }
+
+    // Validation: mismatched lengths, non-increasing r values and single-point tables are rejected
+    #[test]
+    fn invalid_tables_are_rejected() {
+        let params = SimulationParameters::new(String::from("harmonic"), String::from("H"), 10.0, 0.5, 300.0);
+        let run = |r: Vec<f64>, v: Vec<f64>| simulate_tabulated(r, v, 1000.0, &params);
+        assert!(matches!(run(vec![0.0, 1.0, 2.0], vec![1.0, 0.0]), Err(SimError::InvalidInput(_))));
+        assert!(matches!(run(vec![0.0, 1.0, 1.0], vec![1.0, 0.0, 1.0]), Err(SimError::InvalidInput(_))));
+        assert!(matches!(run(vec![0.0, 2.0, 1.0], vec![1.0, 0.0, 1.0]), Err(SimError::InvalidInput(_))));
+        assert!(matches!(run(vec![0.0], vec![1.0]), Err(SimError::InvalidInput(_))));
+    }
+}